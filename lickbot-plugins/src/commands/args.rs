@@ -0,0 +1,306 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::str::FromStr;
+
+use azalea::BlockPos;
+use azalea::registry::{Block, EntityKind, Item};
+use thiserror::Error;
+
+/// The type of a command argument.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArgKind {
+    /// Three integers, `x y z`.
+    BlockPos,
+    Block,
+    Item,
+    EntityKind,
+    Integer,
+    /// The username of a player.
+    Player,
+    /// Any single word.
+    Word,
+    /// The rest of the command, including spaces. Has to be the last argument.
+    Text,
+}
+
+impl ArgKind {
+    /// The number of whitespace separated parts this argument takes up.
+    pub fn width(self) -> usize {
+        match self {
+            ArgKind::BlockPos => 3,
+            _ => 1,
+        }
+    }
+
+    /// Parses exactly [`Self::width`] parts into a value, or at least one for
+    /// [`ArgKind::Text`].
+    pub fn parse(self, parts: &[&str]) -> Result<ArgValue, ArgError> {
+        let wrong_count = match self {
+            ArgKind::Text => parts.is_empty(),
+            _ => parts.len() != self.width(),
+        };
+        if wrong_count {
+            return Err(ArgError::WrongArgCount);
+        }
+
+        let value = match self {
+            ArgKind::BlockPos => {
+                let x = parse_integer(parts[0])?;
+                let y = parse_integer(parts[1])?;
+                let z = parse_integer(parts[2])?;
+                ArgValue::BlockPos(BlockPos::new(x, y, z))
+            }
+            ArgKind::Block => ArgValue::Block(
+                parse_registry(parts[0])
+                    .ok_or_else(|| ArgError::InvalidBlock(parts[0].to_owned()))?,
+            ),
+            ArgKind::Item => ArgValue::Item(
+                parse_registry(parts[0])
+                    .ok_or_else(|| ArgError::InvalidItem(parts[0].to_owned()))?,
+            ),
+            ArgKind::EntityKind => ArgValue::EntityKind(
+                parse_registry(parts[0])
+                    .ok_or_else(|| ArgError::InvalidEntity(parts[0].to_owned()))?,
+            ),
+            ArgKind::Integer => ArgValue::Integer(parse_integer(parts[0])?),
+            ArgKind::Player => ArgValue::Player(parts[0].to_owned()),
+            ArgKind::Word => ArgValue::Word(parts[0].to_owned()),
+            ArgKind::Text => ArgValue::Text(parts.join(" ")),
+        };
+
+        Ok(value)
+    }
+}
+
+/// A single named argument of a command.
+#[derive(Debug, Clone)]
+pub struct Arg {
+    pub name: &'static str,
+    pub kind: ArgKind,
+    /// Optional arguments can only appear at the end of a signature.
+    pub optional: bool,
+}
+
+impl Arg {
+    pub fn new(name: &'static str, kind: ArgKind) -> Self {
+        Self {
+            name,
+            kind,
+            optional: false,
+        }
+    }
+
+    pub fn optional(name: &'static str, kind: ArgKind) -> Self {
+        Self {
+            name,
+            kind,
+            optional: true,
+        }
+    }
+}
+
+impl Display for Arg {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let inner = match self.kind {
+            ArgKind::BlockPos => "x> <y> <z".to_owned(),
+            ArgKind::Text => format!("{}...", self.name),
+            _ => self.name.to_owned(),
+        };
+        if self.optional {
+            write!(f, "[<{inner}>]")
+        } else {
+            write!(f, "<{inner}>")
+        }
+    }
+}
+
+/// A parsed argument value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ArgValue {
+    BlockPos(BlockPos),
+    Block(Block),
+    Item(Item),
+    EntityKind(EntityKind),
+    Integer(i32),
+    Player(String),
+    Word(String),
+    Text(String),
+}
+
+/// The parsed arguments of a command, keyed by argument name.
+#[derive(Debug, Clone, Default)]
+pub struct Args(HashMap<&'static str, ArgValue>);
+
+impl Args {
+    /// Parses the parts of a command against a single signature.
+    pub fn parse(signature: &[Arg], parts: &[&str]) -> Result<Self, ArgError> {
+        let mut args = HashMap::new();
+        let mut remaining = parts;
+
+        for arg in signature {
+            let width = match arg.kind {
+                ArgKind::Text => remaining.len().max(1),
+                _ => arg.kind.width(),
+            };
+            if remaining.len() < width {
+                if arg.optional && remaining.is_empty() {
+                    break;
+                }
+                return Err(ArgError::WrongArgCount);
+            }
+
+            let (current, rest) = remaining.split_at(width);
+            args.insert(arg.name, arg.kind.parse(current)?);
+            remaining = rest;
+        }
+
+        if !remaining.is_empty() {
+            return Err(ArgError::WrongArgCount);
+        }
+
+        Ok(Self(args))
+    }
+
+    pub fn get(&self, name: &str) -> Option<&ArgValue> {
+        self.0.get(name)
+    }
+
+    pub fn has(&self, name: &str) -> bool {
+        self.0.contains_key(name)
+    }
+
+    pub fn block_pos(&self, name: &str) -> Option<BlockPos> {
+        match self.get(name) {
+            Some(ArgValue::BlockPos(pos)) => Some(*pos),
+            _ => None,
+        }
+    }
+
+    pub fn block(&self, name: &str) -> Option<Block> {
+        match self.get(name) {
+            Some(ArgValue::Block(block)) => Some(*block),
+            _ => None,
+        }
+    }
+
+    pub fn item(&self, name: &str) -> Option<Item> {
+        match self.get(name) {
+            Some(ArgValue::Item(item)) => Some(*item),
+            _ => None,
+        }
+    }
+
+    pub fn entity_kind(&self, name: &str) -> Option<EntityKind> {
+        match self.get(name) {
+            Some(ArgValue::EntityKind(kind)) => Some(*kind),
+            _ => None,
+        }
+    }
+
+    pub fn integer(&self, name: &str) -> Option<i32> {
+        match self.get(name) {
+            Some(ArgValue::Integer(value)) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn player(&self, name: &str) -> Option<&str> {
+        match self.get(name) {
+            Some(ArgValue::Player(player)) => Some(player),
+            _ => None,
+        }
+    }
+
+    pub fn word(&self, name: &str) -> Option<&str> {
+        match self.get(name) {
+            Some(ArgValue::Word(word)) => Some(word),
+            _ => None,
+        }
+    }
+
+    pub fn text(&self, name: &str) -> Option<&str> {
+        match self.get(name) {
+            Some(ArgValue::Text(text)) => Some(text),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ArgError {
+    #[error("Invalid block name: {0}")]
+    InvalidBlock(String),
+    #[error("Invalid item name: {0}")]
+    InvalidItem(String),
+    #[error("Invalid entity name: {0}")]
+    InvalidEntity(String),
+    #[error("Invalid number: {0}")]
+    InvalidNumber(String),
+    #[error("Wrong number of arguments")]
+    WrongArgCount,
+}
+
+fn parse_integer(part: &str) -> Result<i32, ArgError> {
+    part.parse()
+        .map_err(|_| ArgError::InvalidNumber(part.to_owned()))
+}
+
+/// Parses a registry name, with or without the `minecraft:` namespace.
+pub fn parse_registry<T: FromStr>(name: &str) -> Option<T> {
+    let name = name.strip_prefix("minecraft:").unwrap_or(name);
+    T::from_str(&format!("minecraft:{name}")).ok()
+}
+
+/// Formats a registry value without the `minecraft:` namespace, e.g. `oak_log`.
+pub fn registry_name(value: &impl Display) -> String {
+    let name = value.to_string();
+    name.strip_prefix("minecraft:").unwrap_or(&name).to_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use azalea::BlockPos;
+    use azalea::registry::{Block, Item};
+
+    use super::{Arg, ArgError, ArgKind, Args};
+
+    #[test]
+    fn test_parse_args() {
+        let signature = [
+            Arg::new("block", ArgKind::Block),
+            Arg::optional("item", ArgKind::Item),
+        ];
+
+        let args = Args::parse(&signature, &["oak_log"]).unwrap();
+        assert_eq!(args.block("block"), Some(Block::OakLog));
+        assert_eq!(args.item("item"), None);
+
+        let args = Args::parse(&signature, &["oak_log", "minecraft:oak_log"]).unwrap();
+        assert_eq!(args.item("item"), Some(Item::OakLog));
+
+        assert!(matches!(
+            Args::parse(&signature, &["not_a_block"]),
+            Err(ArgError::InvalidBlock(_))
+        ));
+        assert!(matches!(
+            Args::parse(&signature, &[]),
+            Err(ArgError::WrongArgCount)
+        ));
+
+        let signature = [Arg::new("pos", ArgKind::BlockPos)];
+        let args = Args::parse(&signature, &["1", "-2", "3"]).unwrap();
+        assert_eq!(args.block_pos("pos"), Some(BlockPos::new(1, -2, 3)));
+        assert!(matches!(
+            Args::parse(&signature, &["1", "2"]),
+            Err(ArgError::WrongArgCount)
+        ));
+
+        let signature = [Arg::new("command", ArgKind::Text)];
+        let args = Args::parse(&signature, &["mine", "oak_log"]).unwrap();
+        assert_eq!(args.text("command"), Some("mine oak_log"));
+        assert!(matches!(
+            Args::parse(&signature, &[]),
+            Err(ArgError::WrongArgCount)
+        ));
+    }
+}
@@ -0,0 +1,330 @@
+pub mod args;
+pub mod permissions;
+pub mod reply;
+
+use std::pin::Pin;
+use std::sync::Arc;
+
+use anyhow::bail;
+use azalea::Client;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+use self::args::{Arg, ArgError, ArgKind, Args};
+use self::permissions::PermissionLevel;
+use self::reply::{Replier, ReplyMode};
+
+pub type CommandFuture = Pin<Box<dyn Future<Output = anyhow::Result<()>> + Send>>;
+type CommandHandler<S> = Arc<dyn Fn(CommandContext<S>) -> CommandFuture + Send + Sync>;
+
+/// A chat command with a name, aliases, typed argument signatures and an async
+/// handler.
+pub struct Command<S> {
+    pub name: &'static str,
+    pub aliases: Vec<&'static str>,
+    pub description: &'static str,
+    /// Every way the command can be called. The first signature that parses
+    /// successfully is used.
+    pub signatures: Vec<Vec<Arg>>,
+    /// The level a sender needs to run this command.
+    pub permission: PermissionLevel,
+
+    handler: CommandHandler<S>,
+}
+
+impl<S> Command<S> {
+    pub fn new<F, Fut>(name: &'static str, handler: F) -> Self
+    where
+        F: Fn(CommandContext<S>) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = anyhow::Result<()>> + Send + 'static,
+    {
+        Self {
+            name,
+            aliases: Vec::new(),
+            description: "",
+            signatures: Vec::new(),
+            permission: PermissionLevel::Trusted,
+            handler: Arc::new(move |ctx| Box::pin(handler(ctx))),
+        }
+    }
+
+    pub fn alias(mut self, alias: &'static str) -> Self {
+        self.aliases.push(alias);
+        self
+    }
+
+    pub fn description(mut self, description: &'static str) -> Self {
+        self.description = description;
+        self
+    }
+
+    /// Sets the level needed to run the command. Defaults to
+    /// [`PermissionLevel::Trusted`].
+    pub fn permission(mut self, permission: PermissionLevel) -> Self {
+        self.permission = permission;
+        self
+    }
+
+    /// Adds a signature. A command without any signatures takes no arguments.
+    pub fn args(mut self, args: impl IntoIterator<Item = Arg>) -> Self {
+        self.signatures.push(args.into_iter().collect());
+        self
+    }
+
+    /// Whether the command is called `name`, either by its name or an alias.
+    pub fn is_called(&self, name: &str) -> bool {
+        self.name == name || self.aliases.contains(&name)
+    }
+
+    /// One usage line per signature, e.g. `!mine <block> [<item>]`.
+    pub fn usage(&self, prefix: &str) -> Vec<String> {
+        if self.signatures.is_empty() {
+            return vec![format!("{prefix}{}", self.name)];
+        }
+
+        self.signatures
+            .iter()
+            .map(|signature| {
+                let mut usage = format!("{prefix}{}", self.name);
+                for arg in signature {
+                    usage.push_str(&format!(" {arg}"));
+                }
+                usage
+            })
+            .collect()
+    }
+
+    /// Parses the parts against every signature of this command.
+    pub fn parse_args(&self, parts: &[&str]) -> Result<Args, ArgError> {
+        if self.signatures.is_empty() {
+            return Args::parse(&[], parts);
+        }
+
+        let mut error = ArgError::WrongArgCount;
+        for signature in &self.signatures {
+            match Args::parse(signature, parts) {
+                Ok(args) => return Ok(args),
+                // a signature with the right number of arguments gives the most
+                // useful error, e.g. an invalid block name
+                Err(ArgError::WrongArgCount) => {}
+                Err(err) => {
+                    if matches!(error, ArgError::WrongArgCount) {
+                        error = err;
+                    }
+                }
+            }
+        }
+
+        Err(error)
+    }
+}
+
+/// Who sent a command.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CommandSender {
+    pub name: Option<String>,
+    pub uuid: Option<Uuid>,
+    pub level: PermissionLevel,
+}
+
+/// Everything a command handler gets access to.
+pub struct CommandContext<S> {
+    pub bot: Client,
+    pub state: S,
+    pub sender: CommandSender,
+    pub args: Args,
+    /// The command without its prefix, e.g. `mine oak_log`, so it can be run
+    /// again later.
+    pub input: String,
+    /// The prefix the command was called with, used to render usages.
+    pub prefix: String,
+    pub registry: Arc<CommandRegistry<S>>,
+    pub replier: Replier,
+}
+
+impl<S: Clone> Clone for CommandContext<S> {
+    fn clone(&self) -> Self {
+        Self {
+            bot: self.bot.clone(),
+            state: self.state.clone(),
+            sender: self.sender.clone(),
+            args: self.args.clone(),
+            input: self.input.clone(),
+            prefix: self.prefix.clone(),
+            registry: self.registry.clone(),
+            replier: self.replier.clone(),
+        }
+    }
+}
+
+impl<S> CommandContext<S> {
+    /// Sends a message back to whoever ran the command.
+    pub fn reply(&self, message: impl AsRef<str>) {
+        self.replier.send(message);
+    }
+}
+
+/// A collection of commands that can be looked up and run by name.
+pub struct CommandRegistry<S> {
+    commands: Vec<Command<S>>,
+}
+
+impl<S> CommandRegistry<S>
+where
+    S: Send + Sync + 'static,
+{
+    /// Creates a registry that contains the `help` command.
+    pub fn new() -> Self {
+        let mut registry = Self {
+            commands: Vec::new(),
+        };
+        registry.register(
+            Command::new("help", help)
+                .description("Lists commands or shows how to use one")
+                .permission(PermissionLevel::Public)
+                .args([Arg::optional("command", ArgKind::Word)]),
+        );
+        registry
+    }
+
+    pub fn register(&mut self, command: Command<S>) -> &mut Self {
+        self.commands.push(command);
+        self
+    }
+
+    /// Finds a command by its name or one of its aliases.
+    pub fn get(&self, name: &str) -> Option<&Command<S>> {
+        self.commands.iter().find(|command| command.is_called(name))
+    }
+
+    pub fn commands(&self) -> impl Iterator<Item = &Command<S>> {
+        self.commands.iter()
+    }
+
+    /// Runs the command in `message` if it starts with `prefix`.
+    /// Messages that are not commands are ignored.
+    ///
+    /// Errors are replied to the sender before being returned.
+    pub async fn run(
+        self: &Arc<Self>,
+        bot: Client,
+        state: S,
+        sender: CommandSender,
+        reply_mode: ReplyMode,
+        prefix: &str,
+        message: &str,
+    ) -> anyhow::Result<()> {
+        let Some(message) = message.strip_prefix(prefix) else {
+            return Ok(());
+        };
+        let parts: Vec<&str> = message.split_whitespace().collect();
+        let Some((name, parts)) = parts.split_first() else {
+            return Ok(());
+        };
+        let Some(command) = self.get(name) else {
+            debug!("unknown command: {name}");
+            return Ok(());
+        };
+
+        let replier = Replier::new(bot.clone(), sender.name.clone(), reply_mode);
+
+        if sender.level < command.permission {
+            info!(
+                "{} tried to run {prefix}{name} without permission",
+                sender.name.as_deref().unwrap_or("unknown sender")
+            );
+            let err = CommandError::PermissionDenied {
+                command: command.name,
+                required: command.permission,
+            };
+            replier.send(err.to_string());
+            return Err(err.into());
+        }
+
+        let args = match command.parse_args(parts) {
+            Ok(args) => args,
+            Err(ArgError::WrongArgCount) => {
+                info!("Invalid arguments for {prefix}{name} command");
+                let err = CommandError::Usage(command.usage(prefix).join(" | "));
+                replier.send(err.to_string());
+                return Err(err.into());
+            }
+            Err(err) => {
+                info!("{err}");
+                replier.send(err.to_string());
+                return Err(err.into());
+            }
+        };
+
+        let ctx = CommandContext {
+            bot,
+            state,
+            sender,
+            args,
+            input: message.trim().to_owned(),
+            prefix: prefix.to_owned(),
+            registry: self.clone(),
+            replier: replier.clone(),
+        };
+        let result = (command.handler)(ctx).await;
+        if let Err(err) = &result {
+            warn!("{prefix}{name} failed: {err}");
+            replier.send(format!("{name} failed: {err}"));
+        }
+        result
+    }
+}
+
+impl<S> Default for CommandRegistry<S>
+where
+    S: Send + Sync + 'static,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum CommandError {
+    #[error("Usage: {0}")]
+    Usage(String),
+    #[error("You need to be {required} to use {command}")]
+    PermissionDenied {
+        command: &'static str,
+        required: PermissionLevel,
+    },
+}
+
+async fn help<S>(ctx: CommandContext<S>) -> anyhow::Result<()>
+where
+    S: Send + Sync + 'static,
+{
+    match ctx.args.word("command") {
+        Some(name) => {
+            let name = name.strip_prefix(&ctx.prefix).unwrap_or(name);
+            let Some(command) = ctx.registry.get(name) else {
+                bail!("Unknown command: {name}");
+            };
+            if !command.description.is_empty() {
+                ctx.reply(format!("{}: {}", command.name, command.description));
+            }
+            ctx.reply(format!("usage: {}", command.usage(&ctx.prefix).join(" | ")));
+            if !command.aliases.is_empty() {
+                ctx.reply(format!("aliases: {}", command.aliases.join(", ")));
+            }
+        }
+        None => {
+            let names: Vec<String> = ctx
+                .registry
+                .commands()
+                .filter(|command| command.permission <= ctx.sender.level)
+                .map(|command| format!("{}{}", ctx.prefix, command.name))
+                .collect();
+            ctx.reply(format!("commands: {}", names.join(", ")));
+        }
+    }
+
+    Ok(())
+}
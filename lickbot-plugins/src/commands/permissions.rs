@@ -0,0 +1,172 @@
+use std::fmt::Display;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::Path;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use uuid::Uuid;
+
+/// How much a player is allowed to do. Levels are ordered, so an owner can run
+/// everything a trusted player can.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum PermissionLevel {
+    #[default]
+    Public,
+    Trusted,
+    Owner,
+}
+
+impl Display for PermissionLevel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PermissionLevel::Public => write!(f, "public"),
+            PermissionLevel::Trusted => write!(f, "trusted"),
+            PermissionLevel::Owner => write!(f, "owner"),
+        }
+    }
+}
+
+impl FromStr for PermissionLevel {
+    type Err = PermissionsError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "public" => Ok(PermissionLevel::Public),
+            "trusted" => Ok(PermissionLevel::Trusted),
+            "owner" => Ok(PermissionLevel::Owner),
+            _ => Err(PermissionsError::InvalidLevel(s.to_owned())),
+        }
+    }
+}
+
+/// The permission level of a single player.
+/// Players are matched by uuid when it is known, otherwise by username.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlayerPermission {
+    pub username: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uuid: Option<Uuid>,
+    pub level: PermissionLevel,
+}
+
+impl PlayerPermission {
+    fn matches(&self, username: Option<&str>, uuid: Option<Uuid>) -> bool {
+        if let (Some(own_uuid), Some(uuid)) = (self.uuid, uuid) {
+            return own_uuid == uuid;
+        }
+
+        username.is_some_and(|username| self.username.eq_ignore_ascii_case(username))
+    }
+}
+
+/// Permission levels of players, persisted as json.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Permissions {
+    #[serde(default)]
+    pub players: Vec<PlayerPermission>,
+}
+
+impl Permissions {
+    /// Loads permissions from a file. A missing file gives empty permissions.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, PermissionsError> {
+        match fs::read_to_string(path) {
+            Ok(contents) => Ok(serde_json::from_str(&contents)?),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), PermissionsError> {
+        let contents = serde_json::to_string_pretty(self)?;
+        fs::write(path, contents)?;
+        Ok(())
+    }
+
+    /// Returns the highest level the player has been given.
+    pub fn level(&self, username: Option<&str>, uuid: Option<Uuid>) -> PermissionLevel {
+        self.players
+            .iter()
+            .filter(|player| player.matches(username, uuid))
+            .map(|player| player.level)
+            .max()
+            .unwrap_or_default()
+    }
+
+    /// Sets the level of a player, replacing any previous entries for them.
+    pub fn set(&mut self, username: &str, uuid: Option<Uuid>, level: PermissionLevel) {
+        self.remove(username, uuid);
+        if level == PermissionLevel::Public {
+            return;
+        }
+
+        self.players.push(PlayerPermission {
+            username: username.to_owned(),
+            uuid,
+            level,
+        });
+    }
+
+    /// Removes every entry for a player, making them public.
+    pub fn remove(&mut self, username: &str, uuid: Option<Uuid>) {
+        self.players
+            .retain(|player| !player.matches(Some(username), uuid));
+    }
+
+    pub fn has_owner(&self) -> bool {
+        self.players
+            .iter()
+            .any(|player| player.level == PermissionLevel::Owner)
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum PermissionsError {
+    #[error("Invalid permission level: {0}")]
+    InvalidLevel(String),
+    #[error("Could not read or write permissions: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Invalid permissions file: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+#[cfg(test)]
+mod tests {
+    use uuid::Uuid;
+
+    use super::{PermissionLevel, Permissions};
+
+    #[test]
+    fn test_permission_levels() {
+        let uuid = Uuid::from_u128(1);
+        let mut permissions = Permissions::default();
+        permissions.set("Owner", None, PermissionLevel::Owner);
+        permissions.set("friend", Some(uuid), PermissionLevel::Trusted);
+
+        assert_eq!(
+            permissions.level(Some("owner"), None),
+            PermissionLevel::Owner
+        );
+        // matched by uuid even after a rename
+        assert_eq!(
+            permissions.level(Some("renamed"), Some(uuid)),
+            PermissionLevel::Trusted
+        );
+        // someone else using the name is not trusted
+        assert_eq!(
+            permissions.level(Some("friend"), Some(Uuid::from_u128(2))),
+            PermissionLevel::Public
+        );
+        assert_eq!(permissions.level(None, None), PermissionLevel::Public);
+
+        permissions.remove("friend", Some(uuid));
+        assert_eq!(
+            permissions.level(Some("friend"), Some(uuid)),
+            PermissionLevel::Public
+        );
+    }
+}
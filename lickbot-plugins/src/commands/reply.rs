@@ -0,0 +1,141 @@
+use azalea::Client;
+use serde::{Deserialize, Serialize};
+
+/// Longest message the server accepts in chat.
+const MAX_CHAT_LENGTH: usize = 256;
+
+/// How replies to a command are sent.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReplyMode {
+    /// Public chat.
+    #[default]
+    Chat,
+    /// `/msg` to the sender. Falls back to public chat if the sender is unknown.
+    Whisper,
+    /// Printed on the terminal running the bot, for commands from its console.
+    Console,
+}
+
+/// Sends replies back to the sender of a command.
+#[derive(Clone)]
+pub struct Replier {
+    bot: Client,
+    recipient: Option<String>,
+    mode: ReplyMode,
+}
+
+impl Replier {
+    pub fn new(bot: Client, recipient: Option<String>, mode: ReplyMode) -> Self {
+        Self {
+            bot,
+            recipient,
+            mode,
+        }
+    }
+
+    pub fn mode(&self) -> ReplyMode {
+        self.mode
+    }
+
+    /// Sends a message, split into several if it is too long for chat.
+    pub fn send(&self, message: impl AsRef<str>) {
+        match (self.mode, &self.recipient) {
+            (ReplyMode::Console, _) => {
+                println!("[{}] {}", self.bot.username(), message.as_ref());
+            }
+            (ReplyMode::Whisper, Some(recipient)) => {
+                let command = format!("/msg {recipient} ");
+                for part in split_message(message.as_ref(), MAX_CHAT_LENGTH - command.len()) {
+                    self.bot.chat(format!("{command}{part}"));
+                }
+            }
+            _ => {
+                // one byte is left for escaping parts that look like commands
+                for part in split_message(message.as_ref(), MAX_CHAT_LENGTH - 1) {
+                    self.bot.chat(escape_command(part));
+                }
+            }
+        }
+    }
+}
+
+/// Keeps a part of a public message from being sent as a command, which
+/// could happen when a `/` from user input ends up at the start of a part.
+/// The server trims the leading space, so it still reads the same.
+fn escape_command(part: String) -> String {
+    if part.starts_with('/') {
+        format!(" {part}")
+    } else {
+        part
+    }
+}
+
+/// Splits a message on whitespace into parts of at most `max_len` bytes.
+/// Words that are too long by themselves are split up.
+fn split_message(message: &str, max_len: usize) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut current = String::new();
+
+    for word in message.split_whitespace() {
+        let mut word = word;
+        while word.len() > max_len {
+            let mut split_at = max_len;
+            while !word.is_char_boundary(split_at) {
+                split_at -= 1;
+            }
+            if !current.is_empty() {
+                parts.push(std::mem::take(&mut current));
+            }
+            parts.push(word[..split_at].to_owned());
+            word = &word[split_at..];
+        }
+        if word.is_empty() {
+            continue;
+        }
+
+        if !current.is_empty() && current.len() + 1 + word.len() > max_len {
+            parts.push(std::mem::take(&mut current));
+        }
+        if !current.is_empty() {
+            current.push(' ');
+        }
+        current.push_str(word);
+    }
+
+    if !current.is_empty() {
+        parts.push(current);
+    }
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{escape_command, split_message};
+
+    #[test]
+    fn test_split_message() {
+        assert_eq!(split_message("mined 3 oak_log", 256), ["mined 3 oak_log"]);
+        assert_eq!(split_message("aaa bbb ccc", 7), ["aaa bbb", "ccc"]);
+        assert_eq!(split_message("  spaced   out  ", 20), ["spaced out"]);
+        assert!(split_message("", 10).is_empty());
+    }
+
+    #[test]
+    fn test_split_message_long_words() {
+        assert_eq!(split_message("a abcdefgh", 3), ["a", "abc", "def", "gh"]);
+        // é is two bytes and isn't cut in half
+        assert_eq!(split_message("éééé", 3), ["é", "é", "é", "é"]);
+        for part in split_message(&"word ".repeat(100), 16) {
+            assert!(part.len() <= 16);
+        }
+    }
+
+    #[test]
+    fn test_escape_command() {
+        let parts = split_message("stopped because /op someone", 16);
+        assert_eq!(parts, ["stopped because", "/op someone"]);
+        let escaped: Vec<String> = parts.into_iter().map(escape_command).collect();
+        assert_eq!(escaped, ["stopped because", " /op someone"]);
+    }
+}
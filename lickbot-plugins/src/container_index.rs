@@ -0,0 +1,445 @@
+//! Remembering what's in every container the bots open, saved to disk so it
+//! can be searched later.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use azalea::app::{App, Plugin};
+use azalea::blocks::BlockTrait;
+use azalea::ecs::prelude::*;
+use azalea::entity::{EyeHeight, LocalEntity, LookDirection, Position};
+use azalea::interact::pick::pick_block;
+use azalea::inventory::{Inventory, ItemStack, Menu};
+use azalea::prelude::*;
+use azalea::registry::Item;
+use azalea::world::{ChunkStorage, InstanceContainer, InstanceName};
+use azalea::{BlockPos, Vec3};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tracing::{debug, warn};
+
+use crate::commands::args::registry_name;
+
+/// How far away the container the bot opened can be.
+const OPEN_REACH: f64 = 5.;
+
+/// What was in a container the last time it was opened.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ContainerContents {
+    /// The name of the block, e.g. `barrel`.
+    pub block: String,
+    /// How many of each item it had, by name.
+    pub items: BTreeMap<String, i32>,
+    /// When it was opened, in seconds since the unix epoch.
+    pub updated: u64,
+}
+
+/// Where some of an item is.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ItemLocation {
+    pub dimension: String,
+    pub pos: BlockPos,
+    pub count: i32,
+}
+
+/// A world and a dimension in it, e.g. `localhost:25565` and
+/// `minecraft:overworld`.
+type WorldKey = (String, String);
+
+#[derive(Debug, Serialize, Deserialize)]
+struct IndexEntry {
+    world: String,
+    dimension: String,
+    pos: [i32; 3],
+    #[serde(flatten)]
+    contents: ContainerContents,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct IndexFile {
+    containers: Vec<IndexEntry>,
+}
+
+/// The contents of every container the bots opened, by world, dimension and
+/// position. Shared by clones, and saved to a json file by
+/// [`Self::flush_periodically`] some time after it changes, since containers
+/// change every tick while items are moved.
+#[derive(Debug, Clone, Default)]
+pub struct ContainerIndex {
+    /// Where the index is saved, or `None` to keep it in memory.
+    path: Option<PathBuf>,
+    containers: Arc<Mutex<HashMap<WorldKey, HashMap<BlockPos, ContainerContents>>>>,
+    /// Whether there are changes that haven't been saved yet.
+    dirty: Arc<AtomicBool>,
+}
+
+impl ContainerIndex {
+    /// Loads the index from a file. A missing file gives an empty index.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ContainerIndexError> {
+        let path = path.as_ref();
+        let file: IndexFile = match fs::read_to_string(path) {
+            Ok(contents) => serde_json::from_str(&contents)?,
+            Err(err) if err.kind() == ErrorKind::NotFound => IndexFile::default(),
+            Err(err) => return Err(err.into()),
+        };
+
+        let mut containers: HashMap<WorldKey, HashMap<BlockPos, ContainerContents>> =
+            HashMap::new();
+        for entry in file.containers {
+            let [x, y, z] = entry.pos;
+            containers
+                .entry((entry.world, entry.dimension))
+                .or_default()
+                .insert(BlockPos::new(x, y, z), entry.contents);
+        }
+        Ok(Self {
+            path: Some(path.to_owned()),
+            containers: Arc::new(Mutex::new(containers)),
+            dirty: Arc::default(),
+        })
+    }
+
+    /// Replaces what's known about a container.
+    pub fn record(&self, world: &str, dimension: &str, pos: BlockPos, contents: ContainerContents) {
+        self.update(|containers| {
+            containers
+                .entry((world.to_owned(), dimension.to_owned()))
+                .or_default()
+                .insert(pos, contents);
+            true
+        });
+    }
+
+    /// Forgets the containers in the loaded chunks that aren't there anymore,
+    /// or were replaced by a different block. Returns how many were
+    /// forgotten.
+    pub fn prune(&self, world: &str, dimension: &str, chunks: &ChunkStorage) -> usize {
+        let key = (world.to_owned(), dimension.to_owned());
+        let mut removed = 0;
+        self.update(|containers| {
+            let Some(dimension) = containers.get_mut(&key) else {
+                return false;
+            };
+            dimension.retain(|pos, contents| {
+                // unloaded chunks could still have it
+                let Some(block_state) = chunks.get_block_state(*pos) else {
+                    return true;
+                };
+                let block: Box<dyn BlockTrait> = block_state.into();
+                let still_there = block.id() == contents.block;
+                if !still_there {
+                    debug!("forgetting the {} at {pos}", contents.block);
+                    removed += 1;
+                }
+                still_there
+            });
+            removed > 0
+        });
+        removed
+    }
+
+    /// Every known container in the world with some of an item, in any
+    /// dimension.
+    pub fn find(&self, world: &str, item: Item) -> Vec<ItemLocation> {
+        let name = registry_name(&item);
+        let containers = self.containers.lock();
+        let mut locations = Vec::new();
+        for ((container_world, dimension), positions) in containers.iter() {
+            if container_world != world {
+                continue;
+            }
+            for (pos, contents) in positions {
+                if let Some(count) = contents.items.get(&name) {
+                    locations.push(ItemLocation {
+                        dimension: dimension.clone(),
+                        pos: *pos,
+                        count: *count,
+                    });
+                }
+            }
+        }
+        locations
+    }
+
+    /// Saves the index if it changed since it was last saved.
+    pub fn flush(&self) {
+        if !self.dirty.swap(false, Ordering::Relaxed) {
+            return;
+        }
+        if let Err(err) = self.save() {
+            warn!("could not save the container index: {err}");
+            // try again next time
+            self.dirty.store(true, Ordering::Relaxed);
+        }
+    }
+
+    /// Saves the index every `interval` if it changed, without blocking the
+    /// async runtime. Runs forever.
+    pub async fn flush_periodically(self, interval: Duration) {
+        loop {
+            tokio::time::sleep(interval).await;
+            let index = self.clone();
+            let _ = tokio::task::spawn_blocking(move || index.flush()).await;
+        }
+    }
+
+    /// Changes the index, and marks it to be saved if `f` returns that
+    /// something changed.
+    fn update(
+        &self,
+        f: impl FnOnce(&mut HashMap<WorldKey, HashMap<BlockPos, ContainerContents>>) -> bool,
+    ) {
+        if f(&mut self.containers.lock()) {
+            self.dirty.store(true, Ordering::Relaxed);
+        }
+    }
+
+    fn save(&self) -> Result<(), ContainerIndexError> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let mut file = IndexFile::default();
+        // copied out so the file is written without holding the lock
+        for ((world, dimension), positions) in self.containers.lock().iter() {
+            for (pos, contents) in positions {
+                file.containers.push(IndexEntry {
+                    world: world.clone(),
+                    dimension: dimension.clone(),
+                    pos: [pos.x, pos.y, pos.z],
+                    contents: contents.clone(),
+                });
+            }
+        }
+        // keep the file stable between saves
+        file.containers
+            .sort_by(|a, b| (&a.world, &a.dimension, a.pos).cmp(&(&b.world, &b.dimension, b.pos)));
+        let contents = serde_json::to_string_pretty(&file)?;
+        fs::write(path, contents)?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ContainerIndexError {
+    #[error("Could not read or write the container index: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Invalid container index: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+/// Records the contents of the containers this bot opens in the index, under
+/// the world it's in. Without this on the bot, nothing is recorded.
+#[derive(Component, Clone, Debug)]
+pub struct ContainerIndexer {
+    pub index: ContainerIndex,
+    /// Which world the bot is in, like the server address.
+    pub world: String,
+}
+
+/// Keeps the index of every bot with a [`ContainerIndexer`] up to date with
+/// the containers it opens, whatever opened them.
+pub struct ContainerIndexPlugin;
+impl Plugin for ContainerIndexPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(GameTick, index_open_containers);
+    }
+}
+
+/// The container menu a bot has open, and what was last recorded from it.
+#[derive(Component, Clone, Debug)]
+pub struct OpenContainer {
+    /// The window id of the menu.
+    id: i32,
+    /// Where the container is and what block it is, or `None` for menus that
+    /// aren't indexed, like furnaces or villager trades.
+    block: Option<(BlockPos, String)>,
+    items: BTreeMap<String, i32>,
+}
+
+/// Whether a block's contents are worth remembering, by block name.
+pub fn is_indexed_block(id: &str) -> bool {
+    matches!(id, "chest" | "trapped_chest" | "barrel" | "ender_chest")
+        || id.ends_with("shulker_box")
+}
+
+/// Records what's in the container menus bots have open, whenever it changes.
+/// The container is the block the bot was looking at when the menu opened.
+/// Ender chests are recorded under where they are, though what's in them
+/// depends on the player.
+#[allow(clippy::type_complexity)]
+pub fn index_open_containers(
+    query: Query<
+        (
+            Entity,
+            &ContainerIndexer,
+            &Inventory,
+            &Position,
+            &EyeHeight,
+            &LookDirection,
+            &InstanceName,
+            Option<&OpenContainer>,
+        ),
+        (With<LocalEntity>, Changed<Inventory>),
+    >,
+    instance_container: Res<InstanceContainer>,
+    mut commands: Commands,
+) {
+    for (entity, indexer, inventory, position, eye_height, look_direction, instance_name, open) in
+        &query
+    {
+        let Some(menu) = &inventory.container_menu else {
+            if open.is_some() {
+                commands.entity(entity).remove::<OpenContainer>();
+            }
+            continue;
+        };
+
+        let block = match open {
+            Some(open) if open.id == inventory.id => open.block.clone(),
+            _ => {
+                let Some(instance) = instance_container.get(instance_name) else {
+                    continue;
+                };
+                let instance = instance.read();
+                let chunks = &instance.chunks;
+                let eye_position = **position + Vec3::new(0., f64::from(**eye_height), 0.);
+                let pos = pick_block(*look_direction, eye_position, chunks, OPEN_REACH).block_pos;
+                chunks
+                    .get_block_state(pos)
+                    .map(|block_state| Box::<dyn BlockTrait>::from(block_state).id().to_owned())
+                    .filter(|id| is_indexed_block(id))
+                    .map(|id| (pos, id))
+            }
+        };
+        let items = container_items(menu);
+        if open.is_some_and(|open| open.id == inventory.id && open.items == items) {
+            continue;
+        }
+
+        if let Some((pos, id)) = &block {
+            let updated = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|time| time.as_secs())
+                .unwrap_or_default();
+            debug!("recording the {id} at {pos}");
+            indexer.index.record(
+                &indexer.world,
+                &instance_name.to_string(),
+                *pos,
+                ContainerContents {
+                    block: id.clone(),
+                    items: items.clone(),
+                    updated,
+                },
+            );
+        }
+        commands.entity(entity).insert(OpenContainer {
+            id: inventory.id,
+            block,
+            items,
+        });
+    }
+}
+
+/// How many of each item are in the container's own slots of a menu.
+fn container_items(menu: &Menu) -> BTreeMap<String, i32> {
+    let mut items = BTreeMap::new();
+    for slot in 0..menu.player_slots_range().start {
+        if let Some(ItemStack::Present(item_data)) = menu.slot(slot) {
+            *items.entry(registry_name(&item_data.kind)).or_default() += item_data.count;
+        }
+    }
+    items
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use azalea::BlockPos;
+    use azalea::registry::Item;
+
+    use super::{ContainerContents, ContainerIndex, is_indexed_block};
+
+    #[test]
+    fn test_find() {
+        let index = ContainerIndex::default();
+        let contents = |items: &[(&str, i32)]| ContainerContents {
+            block: "chest".to_owned(),
+            items: items
+                .iter()
+                .map(|(item, count)| (item.to_string(), *count))
+                .collect::<BTreeMap<_, _>>(),
+            updated: 0,
+        };
+        let pos = BlockPos::new(1, 64, 1);
+        index.record(
+            "server",
+            "minecraft:overworld",
+            pos,
+            contents(&[("torch", 64)]),
+        );
+        index.record(
+            "server",
+            "minecraft:the_nether",
+            pos,
+            contents(&[("torch", 3), ("coal", 5)]),
+        );
+        index.record(
+            "other",
+            "minecraft:overworld",
+            pos,
+            contents(&[("torch", 1)]),
+        );
+        // opening it again replaces what was there
+        index.record(
+            "server",
+            "minecraft:overworld",
+            pos,
+            contents(&[("torch", 10)]),
+        );
+
+        let mut found = index.find("server", Item::Torch);
+        found.sort_by(|a, b| a.dimension.cmp(&b.dimension));
+        let counts: Vec<i32> = found.iter().map(|location| location.count).collect();
+        assert_eq!(counts, [10, 3]);
+        assert!(index.find("server", Item::Dirt).is_empty());
+
+        assert!(is_indexed_block("red_shulker_box"));
+        assert!(!is_indexed_block("furnace"));
+    }
+
+    #[test]
+    fn test_flush() {
+        let path = std::env::temp_dir().join(format!(
+            "lickbot-test-containers-{}.json",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&path);
+        let index = ContainerIndex::load(&path).unwrap();
+        let contents = ContainerContents {
+            block: "barrel".to_owned(),
+            items: BTreeMap::from([("coal".to_owned(), 12)]),
+            updated: 0,
+        };
+        index.record(
+            "server",
+            "minecraft:overworld",
+            BlockPos::new(3, 70, -2),
+            contents,
+        );
+        // nothing is written until it's flushed
+        assert!(!path.exists());
+        index.flush();
+
+        let loaded = ContainerIndex::load(&path).unwrap();
+        assert_eq!(loaded.find("server", Item::Coal).len(), 1);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
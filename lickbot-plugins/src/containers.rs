@@ -0,0 +1,465 @@
+//! Walking to chests and barrels and moving items between them and the bot's
+//! inventory.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Display;
+
+use azalea::blocks::BlockStates;
+use azalea::container::{ContainerClientExt, ContainerHandle};
+use azalea::inventory::operations::{PickupClick, QuickMoveClick};
+use azalea::inventory::{ItemStack, Menu, components};
+use azalea::registry::{Block, Item};
+use azalea::{BlockPos, Client};
+use thiserror::Error;
+use tracing::{info, warn};
+
+use crate::commands::args::registry_name;
+use crate::goals::ReachBlockPosGoal;
+use crate::inventory::{inventory_counts, num_items_in_slots};
+use crate::mining::{StopPathfindingOnDrop, goto_blocks};
+
+/// Which items to put in containers.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DepositFilter {
+    /// Everything except tools, armor, food and the items named in `keep`.
+    AllButGear { keep: Vec<String> },
+    /// Only one item, leaving at least `keep` of it in the inventory.
+    Item { item: Item, keep: i32 },
+    /// Everything, leaving at least `keep` of each item in the inventory.
+    OverCount { keep: i32 },
+}
+
+/// What [`ContainerExtrasClientExt::deposit_items`] put away.
+#[derive(Debug, Clone, Default)]
+pub struct DepositReport {
+    /// How many of each item went into containers.
+    pub deposited: BTreeMap<String, i32>,
+    /// Containers that filled up.
+    pub full: Vec<BlockPos>,
+    /// Containers that couldn't be used, and why.
+    pub skipped: Vec<(BlockPos, ContainerError)>,
+    /// How many stacks that should've been deposited are still in the
+    /// inventory.
+    pub left: usize,
+}
+
+impl Display for DepositReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.deposited.is_empty() {
+            write!(f, "deposited nothing")?;
+        } else {
+            let deposited = self
+                .deposited
+                .iter()
+                .map(|(item, count)| format!("{count} {item}"))
+                .collect::<Vec<_>>()
+                .join(", ");
+            write!(f, "deposited {deposited}")?;
+        }
+        if self.left > 0 {
+            write!(f, ", {} stacks left over", self.left)?;
+        }
+        if !self.full.is_empty() {
+            write!(f, ", {} containers full", self.full.len())?;
+        }
+        for (pos, err) in &self.skipped {
+            write!(f, ", skipped {} {} {}: {err}", pos.x, pos.y, pos.z)?;
+        }
+        Ok(())
+    }
+}
+
+/// What [`ContainerExtrasClientExt::withdraw_items`] took.
+#[derive(Debug, Clone)]
+pub struct WithdrawReport {
+    pub item: Item,
+    pub wanted: i32,
+    pub withdrawn: i32,
+    /// Containers that couldn't be used, and why.
+    pub skipped: Vec<(BlockPos, ContainerError)>,
+}
+
+impl WithdrawReport {
+    pub fn is_complete(&self) -> bool {
+        self.withdrawn >= self.wanted
+    }
+}
+
+impl Display for WithdrawReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let item = registry_name(&self.item);
+        if self.is_complete() {
+            write!(f, "got {} {item}", self.withdrawn)?;
+        } else {
+            write!(f, "only got {} of {} {item}", self.withdrawn, self.wanted)?;
+        }
+        for (pos, err) in &self.skipped {
+            write!(f, ", skipped {} {} {}: {err}", pos.x, pos.y, pos.z)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Error)]
+pub enum ContainerError {
+    #[error("there's no chest or barrel nearby")]
+    NoContainer,
+    #[error("nothing in the inventory to deposit")]
+    NothingToDeposit,
+    #[error("could not get to it")]
+    Unreachable,
+    #[error("could not open it")]
+    WontOpen,
+}
+
+pub trait ContainerExtrasClientExt {
+    /// Walks to where the container can be reached and opens it. It's closed
+    /// when the handle is dropped.
+    /// Pathfinding is stopped if the future is dropped before it finishes.
+    fn goto_and_open_container(
+        &self,
+        pos: BlockPos,
+    ) -> impl std::future::Future<Output = Result<ContainerHandle, ContainerError>> + Send;
+    /// Puts the items matching the filter into the containers, going through
+    /// them in order and moving on to the next one when one fills up.
+    /// Whole stacks are moved, so a bit more than the filter's `keep` can be
+    /// left over.
+    /// Pathfinding is stopped if the future is dropped before it finishes.
+    fn deposit_items(
+        &self,
+        containers: &[BlockPos],
+        filter: &DepositFilter,
+    ) -> impl std::future::Future<Output = Result<DepositReport, ContainerError>> + Send;
+    /// Takes exactly `count` of an item out of the containers, going through
+    /// them in order until it has enough. Gets as many as it can if there
+    /// aren't enough, or the inventory fills up.
+    /// Pathfinding is stopped if the future is dropped before it finishes.
+    fn withdraw_items(
+        &self,
+        containers: &[BlockPos],
+        item: Item,
+        count: i32,
+    ) -> impl std::future::Future<Output = Result<WithdrawReport, ContainerError>> + Send;
+    /// Chests, trapped chests and barrels within `radius` blocks, closest
+    /// first.
+    fn find_containers(&self, radius: f64) -> Vec<BlockPos>;
+}
+
+impl ContainerExtrasClientExt for Client {
+    async fn goto_and_open_container(
+        &self,
+        pos: BlockPos,
+    ) -> Result<ContainerHandle, ContainerError> {
+        let _guard = StopPathfindingOnDrop(self);
+
+        let chunks = self.world().read().chunks.clone();
+        goto_blocks(self, &[pos], |pos| {
+            ReachBlockPosGoal::new(pos, chunks.clone())
+        })
+        .await
+        .map_err(|_| ContainerError::Unreachable)?;
+        let container = self
+            .open_container_at(pos)
+            .await
+            .ok_or(ContainerError::WontOpen)?;
+        Ok(container)
+    }
+
+    async fn deposit_items(
+        &self,
+        containers: &[BlockPos],
+        filter: &DepositFilter,
+    ) -> Result<DepositReport, ContainerError> {
+        if containers.is_empty() {
+            return Err(ContainerError::NoContainer);
+        }
+        if slots_to_deposit(&self.menu(), filter).is_empty() {
+            return Err(ContainerError::NothingToDeposit);
+        }
+
+        let before = inventory_counts(self);
+        let mut report = DepositReport::default();
+        for &pos in containers {
+            if slots_to_deposit(&self.menu(), filter).is_empty() {
+                break;
+            }
+            let container = match self.goto_and_open_container(pos).await {
+                Ok(container) => container,
+                Err(err) => {
+                    warn!("skipping container at {pos}: {err}");
+                    report.skipped.push((pos, err));
+                    continue;
+                }
+            };
+            let Some(menu) = container.menu() else {
+                report.skipped.push((pos, ContainerError::WontOpen));
+                continue;
+            };
+
+            for slot in slots_to_deposit(&menu, filter) {
+                container.click(QuickMoveClick::Left { slot: slot as u16 });
+            }
+            self.wait_ticks(1).await;
+            let full = container
+                .menu()
+                .is_some_and(|menu| !slots_to_deposit(&menu, filter).is_empty());
+            drop(container);
+            if full {
+                info!("container at {pos} is full");
+                report.full.push(pos);
+            }
+        }
+
+        let after = inventory_counts(self);
+        for (item, count) in before {
+            let deposited = count - after.get(&item).copied().unwrap_or_default();
+            if deposited > 0 {
+                report.deposited.insert(item, deposited);
+            }
+        }
+        report.left = slots_to_deposit(&self.menu(), filter).len();
+        info!("{report}");
+        Ok(report)
+    }
+
+    async fn withdraw_items(
+        &self,
+        containers: &[BlockPos],
+        item: Item,
+        count: i32,
+    ) -> Result<WithdrawReport, ContainerError> {
+        if containers.is_empty() {
+            return Err(ContainerError::NoContainer);
+        }
+
+        let before = count_held(self, item);
+        let mut report = WithdrawReport {
+            item,
+            wanted: count,
+            withdrawn: 0,
+            skipped: Vec::new(),
+        };
+        for &pos in containers {
+            let needed = count - (count_held(self, item) - before);
+            if needed <= 0 {
+                break;
+            }
+            let container = match self.goto_and_open_container(pos).await {
+                Ok(container) => container,
+                Err(err) => {
+                    warn!("skipping container at {pos}: {err}");
+                    report.skipped.push((pos, err));
+                    continue;
+                }
+            };
+            take_from_container(&container, item, needed);
+            self.wait_ticks(1).await;
+            drop(container);
+        }
+
+        report.withdrawn = count_held(self, item) - before;
+        info!("{report}");
+        Ok(report)
+    }
+
+    fn find_containers(&self, radius: f64) -> Vec<BlockPos> {
+        let position = self.position();
+        let world = self.world();
+        let mut containers: Vec<BlockPos> = world
+            .read()
+            .find_blocks(position, &container_blocks())
+            .filter(|pos| pos.center().distance_squared_to(position) <= radius.powi(2))
+            .collect();
+        containers.sort_by(|a, b| {
+            a.center()
+                .distance_squared_to(position)
+                .total_cmp(&b.center().distance_squared_to(position))
+        });
+        containers
+    }
+}
+
+/// The blocks that can be deposited into.
+pub fn container_blocks() -> BlockStates {
+    let mut blocks = BlockStates::from(Block::Chest);
+    blocks
+        .set
+        .extend(BlockStates::from(Block::TrappedChest).set);
+    blocks.set.extend(BlockStates::from(Block::Barrel).set);
+    blocks
+}
+
+/// Whether an item is a tool, armor or food, which the bot needs to keep.
+pub fn is_gear(item: &ItemStack) -> bool {
+    let ItemStack::Present(item_data) = item else {
+        return false;
+    };
+    item_data.get_component::<components::MaxDamage>().is_some()
+        || item_data.get_component::<components::Food>().is_some()
+}
+
+/// How many of an item are in the bot's inventory.
+fn count_held(bot: &Client, item: Item) -> i32 {
+    let menu = bot.menu();
+    num_items_in_slots(&menu.slots()[menu.player_slots_range()], item)
+}
+
+/// Moves up to `needed` of an item from an open container to the inventory.
+/// Whole stacks are shift clicked, and the last stack is split by placing
+/// items one at a time in an empty slot.
+fn take_from_container(container: &ContainerHandle, item: Item, mut needed: i32) {
+    let Some(menu) = container.menu() else {
+        return;
+    };
+    for slot in 0..menu.player_slots_range().start {
+        if needed <= 0 {
+            break;
+        }
+        let count = match menu.slot(slot) {
+            Some(ItemStack::Present(item_data)) if item_data.kind == item => item_data.count,
+            _ => continue,
+        };
+        if count <= needed {
+            container.click(QuickMoveClick::Left { slot: slot as u16 });
+            needed -= count;
+            continue;
+        }
+
+        // the menu is updated as clicks are made, so this sees the slots
+        // that were just filled
+        let empty_slot = container.menu().and_then(|menu| {
+            menu.player_slots_range()
+                .find(|slot| menu.slot(*slot).is_some_and(ItemStack::is_empty))
+        });
+        let Some(empty_slot) = empty_slot else {
+            warn!("no room in the inventory for the rest of the {item}");
+            return;
+        };
+        container.click(PickupClick::Left {
+            slot: Some(slot as u16),
+        });
+        for _ in 0..needed {
+            container.click(PickupClick::Right {
+                slot: Some(empty_slot as u16),
+            });
+        }
+        // put the rest back
+        container.click(PickupClick::Left {
+            slot: Some(slot as u16),
+        });
+        needed = 0;
+    }
+}
+
+/// The player inventory slots of the menu that should go in a container.
+fn slots_to_deposit(menu: &Menu, filter: &DepositFilter) -> Vec<usize> {
+    match filter {
+        DepositFilter::AllButGear { keep } => menu
+            .player_slots_range()
+            .filter(|slot| {
+                menu.slot(*slot).is_some_and(|item| match item {
+                    ItemStack::Present(item_data) => {
+                        !is_gear(item) && !keep.contains(&registry_name(&item_data.kind))
+                    }
+                    ItemStack::Empty => false,
+                })
+            })
+            .collect(),
+        DepositFilter::Item { item, keep } => surplus_slots(menu, *keep, |kind| kind == *item),
+        DepositFilter::OverCount { keep } => surplus_slots(menu, *keep, |_| true),
+    }
+}
+
+/// Slots holding whole stacks that can go without leaving less than `keep`
+/// of their item.
+fn surplus_slots(menu: &Menu, keep: i32, wanted: impl Fn(Item) -> bool) -> Vec<usize> {
+    let stacks: Vec<(usize, Item, i32)> = menu
+        .player_slots_range()
+        .filter_map(|slot| match menu.slot(slot) {
+            Some(ItemStack::Present(item_data)) if wanted(item_data.kind) => {
+                Some((slot, item_data.kind, item_data.count))
+            }
+            _ => None,
+        })
+        .collect();
+
+    let mut totals: HashMap<Item, i32> = HashMap::new();
+    for (_, item, count) in &stacks {
+        *totals.entry(*item).or_default() += count;
+    }
+    let mut slots = Vec::new();
+    for (slot, item, count) in stacks {
+        let total = totals.get_mut(&item).expect("every stack is counted");
+        if *total - count >= keep {
+            *total -= count;
+            slots.push(slot);
+        }
+    }
+    slots
+}
+
+#[cfg(test)]
+mod tests {
+    use azalea::inventory::{DataComponentPatch, ItemStack, ItemStackData, Menu, Player};
+    use azalea::registry::Item;
+
+    use super::{DepositFilter, slots_to_deposit};
+
+    fn menu(stacks: &[(usize, Item, i32)]) -> Menu {
+        let mut menu = Menu::Player(Player::default());
+        for (slot, kind, count) in stacks {
+            *menu.slot_mut(*slot).unwrap() = ItemStack::Present(ItemStackData {
+                kind: *kind,
+                count: *count,
+                component_patch: DataComponentPatch::default(),
+            });
+        }
+        menu
+    }
+
+    #[test]
+    fn test_slots_to_deposit() {
+        let menu = menu(&[
+            (10, Item::Cobblestone, 64),
+            (11, Item::Cobblestone, 20),
+            (12, Item::Dirt, 5),
+            (36, Item::DiamondPickaxe, 1),
+            (37, Item::Bread, 16),
+            (38, Item::Torch, 32),
+        ]);
+
+        // tools, food and the keep list stay
+        let filter = DepositFilter::AllButGear {
+            keep: vec!["torch".to_owned()],
+        };
+        assert_eq!(slots_to_deposit(&menu, &filter), vec![10, 11, 12]);
+        let filter = DepositFilter::AllButGear { keep: Vec::new() };
+        assert_eq!(slots_to_deposit(&menu, &filter), vec![10, 11, 12, 38]);
+
+        // only whole stacks go, leaving at least `keep`
+        let filter = DepositFilter::Item {
+            item: Item::Cobblestone,
+            keep: 30,
+        };
+        assert_eq!(slots_to_deposit(&menu, &filter), vec![11]);
+        let filter = DepositFilter::Item {
+            item: Item::Cobblestone,
+            keep: 10,
+        };
+        assert_eq!(slots_to_deposit(&menu, &filter), vec![10]);
+        let filter = DepositFilter::Item {
+            item: Item::Cobblestone,
+            keep: 100,
+        };
+        assert!(slots_to_deposit(&menu, &filter).is_empty());
+
+        // the count is kept for every item, gear included
+        let filter = DepositFilter::OverCount { keep: 5 };
+        assert_eq!(slots_to_deposit(&menu, &filter), vec![10]);
+        let filter = DepositFilter::OverCount { keep: 0 };
+        assert_eq!(
+            slots_to_deposit(&menu, &filter),
+            vec![10, 11, 12, 36, 37, 38]
+        );
+    }
+}
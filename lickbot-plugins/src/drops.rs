@@ -0,0 +1,300 @@
+//! Picking up everything that dropped from broken blocks or killed mobs,
+//! whatever kind of item it is.
+
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::Display;
+use std::time::{Duration, Instant};
+
+use azalea::ecs::prelude::*;
+use azalea::entity::Position;
+use azalea::entity::metadata::ItemItem;
+use azalea::pathfinder::PathfinderOpts;
+use azalea::prelude::PathfinderClientExt;
+use azalea::world::InstanceName;
+use azalea::{BlockPos, Client, Vec3};
+use tracing::{debug, info};
+
+use crate::goals::StandInBlockGoal;
+use crate::inventory::inventory_counts;
+use crate::mining::{MiningExtrasClientExt, StopPathfindingOnDrop};
+use crate::stats::MiningEvent;
+use crate::unload::FullInventoryPolicy;
+
+/// How long the bot stands where an item was before giving up on it.
+const WAIT_AT_ITEM_TICKS: u32 = 10;
+/// How long walking to a single item can take.
+const MAX_TICKS_PER_ITEM: u32 = 20 * 20;
+
+/// Options for [`MiningExtrasClientExt::collect_drops`].
+#[derive(Debug, Clone)]
+pub struct DropOpts {
+    /// How far from where they came from items are counted as drops.
+    pub radius: f64,
+    /// Items further than this from the bot are left alone.
+    pub max_distance: f64,
+    /// How long to keep collecting before giving up on the rest.
+    pub timeout: Duration,
+    /// How long to wait for drops to appear before looking for them.
+    pub settle_ticks: usize,
+}
+
+impl Default for DropOpts {
+    fn default() -> Self {
+        Self {
+            radius: 4.,
+            max_distance: 32.,
+            timeout: Duration::from_secs(60),
+            settle_ticks: 4,
+        }
+    }
+}
+
+/// What [`MiningExtrasClientExt::collect_drops`] picked up.
+#[derive(Debug, Clone, Default)]
+pub struct DropsReport {
+    /// How many of each item the inventory gained.
+    pub gained: BTreeMap<String, i32>,
+    /// Items that couldn't be reached or were still around at the timeout.
+    pub missed: usize,
+}
+
+impl DropsReport {
+    /// Adds what another collection picked up to this one.
+    pub fn merge(&mut self, other: &DropsReport) {
+        for (item, count) in &other.gained {
+            *self.gained.entry(item.clone()).or_default() += count;
+        }
+        self.missed += other.missed;
+    }
+}
+
+impl Display for DropsReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.gained.is_empty() {
+            write!(f, "picked up nothing")?;
+        } else {
+            let gained = self
+                .gained
+                .iter()
+                .map(|(item, count)| format!("{count} {item}"))
+                .collect::<Vec<_>>()
+                .join(", ");
+            write!(f, "picked up {gained}")?;
+        }
+        if self.missed > 0 {
+            write!(f, ", missed {}", self.missed)?;
+        }
+        Ok(())
+    }
+}
+
+/// How walking to an item went.
+enum Walk {
+    /// Picked up, despawned or moved, so it's worth looking again.
+    Done,
+    /// It's still there but the bot couldn't get it.
+    GaveUp,
+}
+
+/// An item entity on the ground near the bot.
+#[derive(Debug, Clone)]
+struct GroundItem {
+    entity: Entity,
+    position: Vec3,
+    /// Whether the bot would throw it out again.
+    junk: bool,
+}
+
+/// Which items on the ground are drops, remembered between looks since they
+/// can land after the first look and roll away from where they landed.
+#[derive(Debug, Default)]
+struct DropTracker {
+    tracked: HashSet<Entity>,
+    given_up: HashSet<Entity>,
+}
+
+impl DropTracker {
+    /// Starts tracking the items within `radius` of any of the `origins`,
+    /// except junk.
+    fn track(&mut self, items: &[GroundItem], origins: &[Vec3], radius: f64) {
+        for item in items {
+            if !item.junk
+                && origins
+                    .iter()
+                    .any(|origin| origin.distance_squared_to(item.position) <= radius.powi(2))
+            {
+                self.tracked.insert(item.entity);
+            }
+        }
+    }
+
+    /// The nearest drop to walk to, or `None` once every drop was picked up,
+    /// despawned or given up on.
+    fn nearest<'a>(&self, items: &'a [GroundItem], position: Vec3) -> Option<&'a GroundItem> {
+        items
+            .iter()
+            .filter(|item| self.tracked.contains(&item.entity))
+            .filter(|item| !self.given_up.contains(&item.entity))
+            .min_by(|a, b| {
+                a.position
+                    .distance_squared_to(position)
+                    .total_cmp(&b.position.distance_squared_to(position))
+            })
+    }
+
+    fn give_up(&mut self, entity: Entity) {
+        self.given_up.insert(entity);
+    }
+
+    /// How many drops are still on the ground.
+    fn missed(&self, items: &[GroundItem]) -> usize {
+        items
+            .iter()
+            .filter(|item| self.tracked.contains(&item.entity))
+            .count()
+    }
+}
+
+/// Picks up every item that lands within `opts.radius` of any of the
+/// `origins`, nearest first and looking again after each one since items
+/// move, until they're all picked up or gone.
+pub(crate) async fn collect_drops(bot: &Client, origins: &[Vec3], opts: &DropOpts) -> DropsReport {
+    let _guard = StopPathfindingOnDrop(bot);
+
+    bot.wait_ticks(opts.settle_ticks).await;
+    let before = inventory_counts(bot);
+    let started = Instant::now();
+
+    let mut tracker = DropTracker::default();
+    let mut report = DropsReport::default();
+    loop {
+        let items = items_on_ground(bot, opts.max_distance);
+        tracker.track(&items, origins, opts.radius);
+        if started.elapsed() > opts.timeout {
+            break;
+        }
+
+        let Some(item) = tracker.nearest(&items, bot.position()) else {
+            break;
+        };
+        let (entity, item_position) = (item.entity, item.position);
+        if let Walk::GaveUp = walk_to_item(bot, entity, item_position.to_block_pos_floor()).await {
+            debug!("could not pick up item at {item_position}");
+            tracker.give_up(entity);
+        }
+    }
+    report.missed += tracker.missed(&items_on_ground(bot, opts.max_distance));
+
+    let after = inventory_counts(bot);
+    let stats = bot.mining_stats();
+    for (item, count) in after {
+        let gained = count - before.get(&item).copied().unwrap_or_default();
+        if gained > 0 {
+            stats.record(MiningEvent::ItemsGained {
+                item: item.clone(),
+                count: gained,
+            });
+            report.gained.insert(item, gained);
+        }
+    }
+    info!("collected drops: {report}");
+    report
+}
+
+async fn walk_to_item(bot: &Client, entity: Entity, pos: BlockPos) -> Walk {
+    bot.start_goto_with_opts(StandInBlockGoal { pos }, PathfinderOpts::new());
+    // let the pathfinder start before checking whether it's done
+    bot.wait_updates(2).await;
+
+    let mut reached_at = None;
+    for tick in 0..MAX_TICKS_PER_ITEM {
+        bot.wait_ticks(1).await;
+        let item_position = bot
+            .ecs
+            .lock()
+            .get::<Position>(entity)
+            .map(|position| **position);
+        match item_position {
+            Some(item_position) if item_position.to_block_pos_floor() == pos => {}
+            _ => return Walk::Done,
+        }
+
+        // it can take a moment to be picked up after getting there
+        if bot.is_goto_target_reached() {
+            let reached_at = *reached_at.get_or_insert(tick);
+            if tick - reached_at > WAIT_AT_ITEM_TICKS {
+                return Walk::GaveUp;
+            }
+        }
+    }
+    Walk::GaveUp
+}
+
+/// The items on the ground within `max_distance` of the bot.
+fn items_on_ground(bot: &Client, max_distance: f64) -> Vec<GroundItem> {
+    let instance_name = bot.component::<InstanceName>();
+    let position = bot.position();
+    let policy = bot.get_component::<FullInventoryPolicy>();
+
+    let mut item_query = bot
+        .ecs
+        .lock()
+        .query::<(Entity, &ItemItem, &Position, &InstanceName)>();
+    item_query
+        .iter(&bot.ecs.lock())
+        .filter(|(_, item, item_position, item_instance)| {
+            *item_instance == &instance_name
+                && item_position.distance_squared_to(position) <= max_distance.powi(2)
+        })
+        .map(|(entity, item, item_position, _)| GroundItem {
+            entity,
+            position: **item_position,
+            junk: policy.as_ref().is_some_and(|policy| policy.is_junk(item)),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use azalea::Vec3;
+    use azalea::ecs::prelude::*;
+
+    use super::{DropTracker, GroundItem};
+
+    #[test]
+    fn test_drop_tracker() {
+        let mut world = World::new();
+        let mut item = |x: f64, junk: bool| GroundItem {
+            entity: world.spawn_empty().id(),
+            position: Vec3::new(x, 64., 0.),
+            junk,
+        };
+        let near = item(1., false);
+        let far = item(3., false);
+        let junk = item(0., true);
+        let elsewhere = item(20., false);
+
+        let origins = [Vec3::new(2., 64., 0.)];
+        let bot = Vec3::new(0., 64., 0.);
+        let mut tracker = DropTracker::default();
+        let items = vec![near.clone(), far.clone(), junk, elsewhere.clone()];
+        tracker.track(&items, &origins, 4.);
+        assert_eq!(tracker.nearest(&items, bot).unwrap().entity, near.entity);
+
+        // an item that rolled away is still a drop
+        let mut rolled = far.clone();
+        rolled.position = Vec3::new(10., 64., 0.);
+        let items = vec![rolled.clone(), elsewhere.clone()];
+        tracker.track(&items, &origins, 4.);
+        assert_eq!(tracker.nearest(&items, bot).unwrap().entity, far.entity);
+        assert_eq!(tracker.missed(&items), 1);
+
+        tracker.give_up(far.entity);
+        assert!(tracker.nearest(&items, bot).is_none());
+        assert_eq!(tracker.missed(&items), 1);
+
+        // picked up or despawned
+        assert!(tracker.nearest(&[elsewhere], bot).is_none());
+        assert_eq!(tracker.missed(&[]), 0);
+    }
+}
@@ -1,4 +1,6 @@
+use std::fmt::Display;
 use std::ops::{Deref, DerefMut};
+use std::str::FromStr;
 
 use azalea::entity::metadata::AbstractMonster;
 use azalea::entity::{self, Dead, LocalEntity, Position};
@@ -7,6 +9,10 @@ use azalea::registry::{self, EntityKind};
 use azalea::world::{InstanceName, MinecraftEntityId};
 use bevy_ecs::prelude::*;
 use bevy_ecs::system::SystemParam;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::commands::args::{parse_registry, registry_name};
 
 /// A single entity target. This can be a specific entity, a player name, or a
 /// entity type.
@@ -19,9 +25,50 @@ pub enum EntityTarget {
     AllPlayers,
 }
 
+#[derive(Debug, Error)]
+#[error(
+    "Invalid target: {0}, expected monsters, players, player:<name>, id:<id> or an entity name"
+)]
+pub struct InvalidTargetError(String);
+
+/// Parses the forms written by [`Display`], e.g. `monsters`, `player:Steve`,
+/// `id:123` or `zombie`.
+impl FromStr for EntityTarget {
+    type Err = InvalidTargetError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || InvalidTargetError(s.to_owned());
+        let target = match (s, s.split_once(':')) {
+            ("monsters", _) => EntityTarget::AllMonsters,
+            ("players", _) => EntityTarget::AllPlayers,
+            (_, Some(("player", name))) => EntityTarget::PlayerName(name.to_owned()),
+            (_, Some(("id", id))) => {
+                EntityTarget::EntityId(MinecraftEntityId(id.parse().map_err(|_| invalid())?))
+            }
+            _ => EntityTarget::EntityKind(parse_registry(s).ok_or_else(invalid)?),
+        };
+        Ok(target)
+    }
+}
+
+impl Display for EntityTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EntityTarget::EntityKind(kind) => write!(f, "{}", registry_name(kind)),
+            EntityTarget::EntityId(id) => write!(f, "id:{}", id.0),
+            EntityTarget::PlayerName(name) => write!(f, "player:{name}"),
+            EntityTarget::AllMonsters => write!(f, "monsters"),
+            EntityTarget::AllPlayers => write!(f, "players"),
+        }
+    }
+}
+
 /// A collection of entity targets. This is used to find entities that match
 /// the given targets.
-#[derive(Debug, Default, Clone)]
+///
+/// Serialized as a comma separated list, e.g. `monsters,player:Steve`.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
 pub struct EntityTargets(Vec<EntityTarget>);
 
 impl EntityTargets {
@@ -30,6 +77,30 @@ impl EntityTargets {
     }
 }
 
+impl TryFrom<String> for EntityTargets {
+    type Error = InvalidTargetError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value
+            .split(',')
+            .map(str::trim)
+            .filter(|target| !target.is_empty())
+            .map(EntityTarget::from_str)
+            .collect::<Result<_, _>>()
+            .map(Self)
+    }
+}
+
+impl From<EntityTargets> for String {
+    fn from(targets: EntityTargets) -> Self {
+        targets
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+}
+
 impl Deref for EntityTargets {
     type Target = Vec<EntityTarget>;
 
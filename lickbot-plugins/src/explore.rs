@@ -0,0 +1,227 @@
+//! Walking out to unexplored areas until blocks to mine show up in the loaded
+//! chunks.
+
+use std::time::{Duration, Instant};
+
+use azalea::blocks::BlockStates;
+use azalea::pathfinder::PathfinderOpts;
+use azalea::pathfinder::goals::{AndGoal, XZGoal};
+use azalea::prelude::PathfinderClientExt;
+use azalea::{BlockPos, Client};
+use thiserror::Error;
+use tracing::{debug, info};
+
+use crate::goals::YRangeGoal;
+use crate::mining::{MiningExtrasClientExt, StopPathfindingOnDrop};
+use crate::stats::MiningEvent;
+use crate::targets::TargetSearch;
+use crate::vein::vein_kind;
+
+/// How often the loaded chunks are searched while walking.
+const CHECK_EVERY_TICKS: usize = 20;
+/// How long the bot can go without moving before a waypoint is skipped.
+const STUCK_TICKS: usize = 10 * 20;
+/// How far above or below `y_level` the bot can end up at a waypoint, so it
+/// doesn't have to dig to one exact block.
+const Y_LEVEL_TOLERANCE: i32 = 4;
+
+/// Options for [`MiningExtrasClientExt::explore_for_blocks`].
+#[derive(Debug, Clone)]
+pub struct ExploreOpts {
+    /// How far apart the waypoints of the spiral are, in blocks. Around the
+    /// view distance works best, so every leg loads new chunks.
+    pub step: i32,
+    /// How far from where it started the bot goes, in blocks.
+    pub max_radius: i32,
+    /// How long exploring can take in total.
+    pub time_budget: Duration,
+    /// The y level to explore at, like the one an ore is most common at.
+    /// Otherwise the bot stays at whatever height the terrain is.
+    pub y_level: Option<i32>,
+}
+
+impl Default for ExploreOpts {
+    fn default() -> Self {
+        Self {
+            step: 32,
+            max_radius: 256,
+            time_budget: Duration::from_secs(5 * 60),
+            y_level: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Error)]
+pub enum ExploreError {
+    #[error("explored {0} blocks out without finding any")]
+    NotFound(i32),
+    #[error("explored for {}s without finding any", .0.as_secs())]
+    TimedOut(Duration),
+}
+
+/// The y level an ore is most common at, for exploring at the right height.
+/// Deepslate variants are the same ore.
+pub fn ore_y_level(block: &str) -> Option<i32> {
+    Some(match vein_kind(block) {
+        "coal_ore" => 95,
+        "copper_ore" => 48,
+        "iron_ore" => 16,
+        "lapis_ore" => 0,
+        "gold_ore" => -16,
+        "redstone_ore" | "diamond_ore" => -58,
+        "emerald_ore" => 100,
+        "ancient_debris" => 15,
+        _ => return None,
+    })
+}
+
+/// Waypoints going outward from `center` in a square spiral, `step` blocks
+/// apart, until they're more than `max_radius` away on either axis.
+pub fn spiral(center: (i32, i32), step: i32, max_radius: i32) -> Vec<(i32, i32)> {
+    let (x0, z0) = center;
+    let rings = max_radius / step.max(1);
+    let mut waypoints = Vec::new();
+    for ring in 1..=rings {
+        let r = ring * step;
+        // walk around the ring starting from its north-west corner
+        for i in 0..ring * 2 {
+            waypoints.push((x0 - r + i * step, z0 - r));
+        }
+        for i in 0..ring * 2 {
+            waypoints.push((x0 + r, z0 - r + i * step));
+        }
+        for i in 0..ring * 2 {
+            waypoints.push((x0 + r - i * step, z0 + r));
+        }
+        for i in 0..ring * 2 {
+            waypoints.push((x0 - r, z0 + r - i * step));
+        }
+    }
+    waypoints
+}
+
+/// How walking to a waypoint went.
+enum Leg {
+    Found(Vec<BlockPos>),
+    Reached,
+    Stuck,
+    OutOfTime,
+}
+
+pub(crate) async fn explore_for_blocks(
+    bot: &Client,
+    blocks: &BlockStates,
+    search: &TargetSearch,
+    opts: &ExploreOpts,
+) -> Result<Vec<BlockPos>, ExploreError> {
+    let _guard = StopPathfindingOnDrop(bot);
+
+    let found = bot.find_mining_targets(blocks, search);
+    if !found.is_empty() {
+        return Ok(found);
+    }
+
+    let started = Instant::now();
+    let origin = bot.position().to_block_pos_floor();
+    let waypoints = spiral((origin.x, origin.z), opts.step, opts.max_radius);
+    info!("exploring {} waypoints around {origin}", waypoints.len());
+
+    let result = 'explore: {
+        for (x, z) in waypoints {
+            let deadline = started + opts.time_budget;
+            match walk_to_waypoint(bot, blocks, search, (x, z), opts.y_level, deadline).await {
+                Leg::Found(found) => break 'explore Ok(found),
+                Leg::Reached => debug!("explored to {x} {z}"),
+                Leg::Stuck => debug!("skipping waypoint {x} {z}, got stuck"),
+                Leg::OutOfTime => break 'explore Err(ExploreError::TimedOut(opts.time_budget)),
+            }
+        }
+        Err(ExploreError::NotFound(opts.max_radius))
+    };
+    bot.mining_stats()
+        .record(MiningEvent::Pathing(started.elapsed()));
+    result
+}
+
+async fn walk_to_waypoint(
+    bot: &Client,
+    blocks: &BlockStates,
+    search: &TargetSearch,
+    (x, z): (i32, i32),
+    y_level: Option<i32>,
+    deadline: Instant,
+) -> Leg {
+    match y_level {
+        Some(y) => bot.start_goto_with_opts(
+            AndGoal(
+                XZGoal { x, z },
+                YRangeGoal {
+                    min: y - Y_LEVEL_TOLERANCE,
+                    max: y + Y_LEVEL_TOLERANCE,
+                },
+            ),
+            PathfinderOpts::new(),
+        ),
+        None => bot.start_goto_with_opts(XZGoal { x, z }, PathfinderOpts::new()),
+    }
+    // let the pathfinder start before checking whether it's done
+    bot.wait_updates(2).await;
+
+    let mut last_moved = (bot.position(), 0);
+    let mut ticks = 0;
+    loop {
+        bot.wait_ticks(CHECK_EVERY_TICKS).await;
+        ticks += CHECK_EVERY_TICKS;
+
+        let found = bot.find_mining_targets(blocks, search);
+        if !found.is_empty() {
+            bot.stop_pathfinding();
+            return Leg::Found(found);
+        }
+        if Instant::now() > deadline {
+            bot.stop_pathfinding();
+            return Leg::OutOfTime;
+        }
+        if bot.is_goto_target_reached() {
+            return Leg::Reached;
+        }
+
+        let position = bot.position();
+        if position.distance_squared_to(last_moved.0) > 1. {
+            last_moved = (position, ticks);
+        } else if ticks - last_moved.1 > STUCK_TICKS {
+            bot.stop_pathfinding();
+            return Leg::Stuck;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ore_y_level, spiral};
+
+    #[test]
+    fn test_spiral() {
+        let waypoints = spiral((0, 0), 10, 20);
+        assert_eq!(waypoints.len(), 8 + 16);
+        assert_eq!(waypoints[0], (-10, -10));
+        assert!(
+            waypoints[..8]
+                .iter()
+                .all(|(x, z)| x.abs().max(z.abs()) == 10)
+        );
+        assert!(
+            waypoints[8..]
+                .iter()
+                .all(|(x, z)| x.abs().max(z.abs()) == 20)
+        );
+        assert!(spiral((0, 0), 32, 16).is_empty());
+    }
+
+    #[test]
+    fn test_ore_y_level() {
+        assert_eq!(ore_y_level("deepslate_diamond_ore"), Some(-58));
+        assert_eq!(ore_y_level("iron_ore"), Some(16));
+        assert_eq!(ore_y_level("oak_log"), None);
+    }
+}
@@ -2,7 +2,7 @@ use std::fmt::Debug;
 
 use azalea::bot::direction_looking_at;
 use azalea::interact::pick::pick_block;
-use azalea::pathfinder::goals::{BlockPosGoal, Goal};
+use azalea::pathfinder::goals::{BlockPosGoal, Goal, YGoal};
 use azalea::world::ChunkStorage;
 use azalea::{BlockPos, Vec3};
 
@@ -124,3 +124,24 @@ impl Goal for StandInBlockGoal {
         n == self.pos || n == self.pos.down(1)
     }
 }
+
+/// Move to any y level between `min` and `max`, inclusive, wherever that is.
+#[derive(Clone, Debug)]
+pub struct YRangeGoal {
+    pub min: i32,
+    pub max: i32,
+}
+impl Goal for YRangeGoal {
+    fn heuristic(&self, n: BlockPos) -> f32 {
+        if self.success(n) {
+            return 0.;
+        }
+        YGoal {
+            y: n.y.clamp(self.min, self.max),
+        }
+        .heuristic(n)
+    }
+    fn success(&self, n: BlockPos) -> bool {
+        (self.min..=self.max).contains(&n.y)
+    }
+}
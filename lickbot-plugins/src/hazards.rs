@@ -0,0 +1,243 @@
+//! Checks for what can go wrong when a block is broken: lava or water pouring
+//! in, gravel falling on the bot, a long fall, or a cave with mobs in it.
+
+use azalea::blocks::BlockTrait;
+use azalea::ecs::prelude::*;
+use azalea::entity::metadata::AbstractMonster;
+use azalea::entity::{Dead, EyeHeight, Position};
+use azalea::registry::EntityKind;
+use azalea::world::{ChunkStorage, InstanceName};
+use azalea::{BlockPos, Client, Vec3};
+
+use crate::commands::args::registry_name;
+use crate::mining::MiningError;
+use crate::vein::neighbor_offsets;
+
+/// How far the bot can fall without taking damage.
+const MAX_SAFE_FALL: i32 = 3;
+/// How far away a hostile mob has to be to be ignored.
+const HOSTILE_VIEW_DISTANCE: f64 = 16.;
+
+/// Whether a block falls when there's nothing under it.
+pub fn is_falling_block(id: &str) -> bool {
+    matches!(
+        id,
+        "gravel"
+            | "sand"
+            | "red_sand"
+            | "suspicious_sand"
+            | "suspicious_gravel"
+            | "anvil"
+            | "chipped_anvil"
+            | "damaged_anvil"
+    ) || id.ends_with("_concrete_powder")
+}
+
+fn block_id_at(chunks: &ChunkStorage, pos: BlockPos) -> Option<&'static str> {
+    let block_state = chunks.get_block_state(pos)?;
+    let block: Box<dyn BlockTrait> = block_state.into();
+    Some(block.id())
+}
+
+/// Checks whether breaking the block at `pos` is dangerous for a bot with its
+/// feet at `feet`, going by the blocks around it.
+pub fn check_block_hazards(
+    pos: BlockPos,
+    feet: BlockPos,
+    chunks: &ChunkStorage,
+) -> Result<(), MiningError> {
+    for offset in neighbor_offsets(false) {
+        let neighbor = pos + offset;
+        match block_id_at(chunks, neighbor) {
+            Some("lava") => return Err(MiningError::ExposesLava(neighbor)),
+            // water can't flow up into the hole
+            Some("water") if offset.y >= 0 => return Err(MiningError::ExposesWater(neighbor)),
+            _ => {}
+        }
+    }
+
+    let above = pos.up(1);
+    if pos.x == feet.x
+        && pos.z == feet.z
+        && pos.y > feet.y
+        && block_id_at(chunks, above).is_some_and(is_falling_block)
+    {
+        return Err(MiningError::FallingBlockAbove(above));
+    }
+
+    if pos == feet.down(1) {
+        // the bot drops into the hole, and then through anything under it
+        let mut fall = 1;
+        let mut below = pos.down(1);
+        loop {
+            let Some(block_state) = chunks.get_block_state(below) else {
+                return Err(MiningError::LongFall);
+            };
+            if !block_state.is_air() {
+                if block_id_at(chunks, below) == Some("lava") {
+                    return Err(MiningError::ExposesLava(below));
+                }
+                break;
+            }
+            fall += 1;
+            if fall > MAX_SAFE_FALL {
+                return Err(MiningError::LongFall);
+            }
+            below = below.down(1);
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks the blocks around `pos`, and whether breaking it would open into a
+/// cave with a hostile mob that can see into it.
+pub fn check_hazards(bot: &Client, pos: &BlockPos) -> Result<(), MiningError> {
+    let instance_name = bot.component::<InstanceName>();
+    let mut monster_query = bot.ecs.lock().query_filtered::<(
+        &EntityKind,
+        &Position,
+        &EyeHeight,
+        &InstanceName,
+    ), (With<AbstractMonster>, Without<Dead>)>();
+    let monsters: Vec<(EntityKind, Vec3)> = monster_query
+        .iter(&bot.ecs.lock())
+        .filter(|(_, position, _, monster_instance)| {
+            *monster_instance == &instance_name
+                && position.distance_squared_to(pos.center()) < HOSTILE_VIEW_DISTANCE.powi(2)
+        })
+        .map(|(kind, position, eye_height, _)| {
+            (
+                *kind,
+                **position + Vec3::new(0., f64::from(**eye_height), 0.),
+            )
+        })
+        .collect();
+
+    let eye_position = bot.eye_position();
+    let feet = bot.position().to_block_pos_floor();
+    let world = bot.world();
+    let world = world.read();
+    check_block_hazards(*pos, feet, &world.chunks)?;
+
+    // air behind the block, away from the bot, is a cave it would open into
+    let distance_to_bot = pos.center().distance_squared_to(eye_position);
+    let behind = neighbor_offsets(false)
+        .into_iter()
+        .map(|offset| *pos + offset)
+        .filter(|neighbor| {
+            neighbor.center().distance_squared_to(eye_position) > distance_to_bot
+                && world
+                    .chunks
+                    .get_block_state(*neighbor)
+                    .is_some_and(|block_state| block_state.is_air())
+        });
+    for cave in behind {
+        for (kind, monster_eye) in &monsters {
+            if line_of_sight(&world.chunks, *monster_eye, cave.center()) {
+                return Err(MiningError::HostileInView(registry_name(kind)));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Whether there are only air blocks between two points.
+fn line_of_sight(chunks: &ChunkStorage, from: Vec3, to: Vec3) -> bool {
+    let distance = from.distance_squared_to(to).sqrt();
+    let steps = (distance * 4.).ceil() as usize;
+    let target = to.to_block_pos_floor();
+    (1..steps).all(|step| {
+        let point = from + (to - from) * (step as f64 / steps as f64);
+        let block_pos = point.to_block_pos_floor();
+        block_pos == target
+            || chunks
+                .get_block_state(block_pos)
+                .is_none_or(|block_state| block_state.is_air())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use azalea::BlockPos;
+    use azalea::blocks::BlockState;
+    use azalea::core::position::ChunkPos;
+    use azalea::registry::Block;
+    use azalea::world::{Chunk, ChunkStorage, PartialChunkStorage};
+
+    use super::check_block_hazards;
+    use crate::mining::MiningError;
+
+    /// A single loaded chunk of air with the given blocks in it.
+    fn chunks(blocks: &[(BlockPos, Block)]) -> ChunkStorage {
+        let mut chunks = ChunkStorage::default();
+        let mut partial = PartialChunkStorage::default();
+        partial.set(&ChunkPos::new(0, 0), Some(Chunk::default()), &mut chunks);
+        for (pos, block) in blocks {
+            chunks.set_block_state(*pos, BlockState::from(*block));
+        }
+        chunks
+    }
+
+    #[test]
+    fn test_check_block_hazards() {
+        let pos = BlockPos::new(4, 64, 4);
+        let feet = BlockPos::new(5, 64, 4);
+
+        let safe = chunks(&[(pos, Block::Stone), (pos.down(1), Block::Stone)]);
+        assert!(check_block_hazards(pos, feet, &safe).is_ok());
+
+        let lava = chunks(&[(pos, Block::Stone), (pos.north(1), Block::Lava)]);
+        assert!(matches!(
+            check_block_hazards(pos, feet, &lava),
+            Err(MiningError::ExposesLava(neighbor)) if neighbor == pos.north(1)
+        ));
+
+        // water only flows in from the sides or above
+        let water = chunks(&[(pos, Block::Stone), (pos.up(1), Block::Water)]);
+        assert!(matches!(
+            check_block_hazards(pos, feet, &water),
+            Err(MiningError::ExposesWater(_))
+        ));
+        let water = chunks(&[(pos, Block::Stone), (pos.down(1), Block::Water)]);
+        assert!(check_block_hazards(pos, feet, &water).is_ok());
+    }
+
+    #[test]
+    fn test_falling_blocks() {
+        let feet = BlockPos::new(4, 64, 4);
+        let head = feet.up(1);
+        let gravel = chunks(&[(head, Block::Stone), (head.up(1), Block::Gravel)]);
+        assert!(matches!(
+            check_block_hazards(head, feet, &gravel),
+            Err(MiningError::FallingBlockAbove(_))
+        ));
+        // it only falls on the bot if it's over its head
+        let beside = head.east(1);
+        let gravel = chunks(&[(beside, Block::Stone), (beside.up(1), Block::Gravel)]);
+        assert!(check_block_hazards(beside, feet, &gravel).is_ok());
+    }
+
+    #[test]
+    fn test_long_fall() {
+        let feet = BlockPos::new(4, 64, 4);
+        let floor = feet.down(1);
+
+        // landing two blocks down is fine
+        let short = chunks(&[(floor, Block::Stone), (floor.down(3), Block::Stone)]);
+        assert!(check_block_hazards(floor, feet, &short).is_ok());
+
+        let long = chunks(&[(floor, Block::Stone), (floor.down(5), Block::Stone)]);
+        assert!(matches!(
+            check_block_hazards(floor, feet, &long),
+            Err(MiningError::LongFall)
+        ));
+
+        let lava = chunks(&[(floor, Block::Stone), (floor.down(2), Block::Lava)]);
+        assert!(matches!(
+            check_block_hazards(floor, feet, &lava),
+            Err(MiningError::ExposesLava(_))
+        ));
+    }
+}
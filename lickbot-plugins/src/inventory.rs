@@ -1,6 +1,13 @@
-use azalea::inventory::ItemStack;
+use std::collections::BTreeMap;
+
+use azalea::Client;
+use azalea::ecs::prelude::*;
+use azalea::inventory::operations::{ClickOperation, ThrowClick};
+use azalea::inventory::{ContainerClickEvent, Inventory, ItemStack};
 use azalea::registry::Item;
 
+use crate::commands::args::registry_name;
+
 pub fn num_items_in_slots(slots: &[ItemStack], item: Item) -> i32 {
     slots
         .iter()
@@ -17,6 +24,54 @@ pub fn num_items_in_slots(slots: &[ItemStack], item: Item) -> i32 {
         .sum()
 }
 
+/// How many of each item are in the bot's inventory, by name.
+pub fn inventory_counts(bot: &Client) -> BTreeMap<String, i32> {
+    let menu = bot.menu();
+    let mut counts = BTreeMap::new();
+    for item in &menu.slots()[menu.player_slots_range()] {
+        if let ItemStack::Present(item_data) = item {
+            *counts.entry(registry_name(&item_data.kind)).or_default() += item_data.count;
+        }
+    }
+    counts
+}
+
+/// Throws `count` of an item out of the inventory, where the bot is looking.
+/// Returns how many were thrown, which is less if there weren't enough.
+pub async fn throw_items(bot: &Client, item: Item, count: i32) -> i32 {
+    let inventory = bot.component::<Inventory>();
+    let menu = &inventory.inventory_menu;
+    let mut remaining = count;
+    for slot in menu.player_slots_range() {
+        if remaining <= 0 {
+            break;
+        }
+        let stack_count = match menu.slot(slot) {
+            Some(ItemStack::Present(item_data)) if item_data.kind == item => item_data.count,
+            _ => continue,
+        };
+
+        let slot = slot as u16;
+        let clicks: Vec<ThrowClick> = if stack_count <= remaining {
+            vec![ThrowClick::All { slot }]
+        } else {
+            (0..remaining)
+                .map(|_| ThrowClick::Single { slot })
+                .collect()
+        };
+        remaining -= stack_count.min(remaining);
+        for click in clicks {
+            bot.ecs.lock().trigger(ContainerClickEvent {
+                entity: bot.entity,
+                window_id: inventory.id,
+                operation: ClickOperation::Throw(click),
+            });
+            bot.wait_ticks(1).await;
+        }
+    }
+    count - remaining
+}
+
 #[cfg(test)]
 mod tests {
     use azalea::inventory::{DataComponentPatch, ItemStack, ItemStackData};
@@ -1,7 +1,24 @@
+pub mod commands;
+pub mod container_index;
+pub mod containers;
+pub mod drops;
+pub mod entity_target;
+pub mod explore;
 pub mod goals;
+pub mod hazards;
 pub mod inventory;
 pub mod mining;
 pub mod nearest_entity;
+pub mod quarry;
+pub mod stats;
+pub mod targets;
+pub mod task_queue;
+pub mod tasks;
+pub mod toggle;
+pub mod tools;
+pub mod tunnel;
+pub mod unload;
+pub mod vein;
 pub mod weapon;
 
 pub mod plugins;
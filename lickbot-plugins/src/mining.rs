@@ -1,30 +1,59 @@
+use std::collections::HashSet;
 use std::error::Error;
-use std::fmt::Display;
+use std::fmt::{Debug, Display};
+use std::time::{Duration, Instant};
 
 use azalea::auto_tool::AutoToolClientExt;
-use azalea::blocks::BlockTrait;
+use azalea::blocks::{BlockState, BlockStates, BlockTrait};
 use azalea::bot::{BotClientExt, direction_looking_at};
 use azalea::ecs::prelude::*;
-use azalea::entity::Position;
+use azalea::entity::dimensions::EntityDimensions;
+use azalea::entity::{Dead, Position};
 use azalea::interact::pick::pick_block;
+use azalea::inventory::operations::{ClickOperation, SwapClick};
+use azalea::inventory::{ContainerClickEvent, Inventory, SetSelectedHotbarSlotEvent};
 use azalea::pathfinder::PathfinderOpts;
-use azalea::pathfinder::goals::OrGoals;
+use azalea::pathfinder::goals::{Goal, OrGoals};
 use azalea::prelude::PathfinderClientExt;
-use azalea::registry::Item;
-use azalea::world::ChunkStorage;
+use azalea::registry::{EntityKind, Item};
+use azalea::world::{ChunkStorage, InstanceName};
 use azalea::{BlockPos, Client, Vec3};
 use thiserror::Error;
 use tokio::sync::broadcast::error::RecvError;
+use tokio::time::timeout;
 use tracing::{debug, info, warn};
 
+use crate::commands::args::{parse_registry, registry_name};
+use crate::drops::{self, DropOpts, DropsReport};
+use crate::explore::{self, ExploreError, ExploreOpts};
 use crate::goals::{ReachBlockPosGoal, StandInBlockGoal, StandNextToBlockGoal};
+use crate::hazards::check_hazards;
 use crate::inventory::num_items_in_slots;
+use crate::quarry::{Cuboid, QuarryOpts, QuarryProgress, should_quarry};
+use crate::stats::{MiningEvent, MiningStatsTracker};
+use crate::targets::{TargetSearch, rank_targets};
+use crate::tools::{ToolChoice, ToolPolicy, ToolProtection, best_tool_for_block, total_damage};
+use crate::tunnel::{self, TunnelOpts, TunnelReport};
+use crate::unload::{self, FullInventoryPolicy, UnloadError};
+use crate::vein::{find_vein, plan_route, vein_kind_at};
 
 use super::nearest_entity::NearestEntityClientExt;
 
+/// How long walking to blocks to mine them can take.
+const GOTO_TIMEOUT: Duration = Duration::from_secs(60);
+/// How many ticks picking up an item can take.
+const PICK_UP_TIMEOUT_TICKS: u32 = 30 * 20;
+/// How many blocks are listed in [`CantMineAnyError`] messages.
+const MAX_LISTED_FAILURES: usize = 5;
+
 pub trait MiningExtrasClientExt {
     //// Mines a block with the best tool in hotbar.
-    /// Also checks whether the block is mineable.
+    /// Also checks whether the block is mineable, and refuses to mine it if
+    /// that would be dangerous, see [`check_hazards`].
+    /// With [`ToolProtection`] on the bot, tools that are nearly broken aren't
+    /// used and backups are taken from the whole inventory.
+    /// With [`ToolPolicy`] on the bot, tools with Fortune or Silk Touch are
+    /// picked for the blocks that benefit and saved for the rest.
     fn mine_block_with_best_tool(
         &self,
         pos: &BlockPos,
@@ -34,7 +63,8 @@ pub trait MiningExtrasClientExt {
         &self,
         blocks_pos: &[BlockPos],
     ) -> impl Future<Output = Result<(), CantMineAnyError>> + Send;
-    /// Mines a checks whether the block is mineable and then mines it.
+    /// Mines a checks whether the block is mineable and safe to mine and then
+    /// mines it.
     fn checked_mine(&self, pos: &BlockPos) -> impl Future<Output = Result<(), MiningError>> + Send;
     /// Goto the block and try to mine it.
     /// Will retry 3 times.
@@ -43,23 +73,94 @@ pub trait MiningExtrasClientExt {
         pos: &BlockPos,
     ) -> impl Future<Output = Result<(), CantMineAnyError>> + Send;
     /// Will mine the easiest to reach of the blocks in the list.
-    /// Will retry 3 times.
+    /// Tries from where the bot is first, then walks closer up to 3 times.
+    /// Pathfinding is stopped if the future is dropped before it finishes.
     fn goto_and_try_mine_blocks(
         &self,
         blocks_pos: &[BlockPos],
     ) -> impl std::future::Future<Output = Result<(), CantMineAnyError>> + Send;
+    /// Picks up the nearest item of the given kind.
+    /// Pathfinding is stopped if the future is dropped before it finishes.
     fn pick_up_item(
         &self,
         item: Item,
     ) -> impl std::future::Future<Output = Result<(), NoItemsError>> + Send;
+    /// Mines every block connected to `start` that is the same kind as it,
+    /// walking as little as it can, then picks up what dropped.
+    /// Pathfinding is stopped if the future is dropped before it finishes.
+    fn mine_vein(
+        &self,
+        start: &BlockPos,
+        opts: &VeinOpts,
+    ) -> impl std::future::Future<Output = Result<VeinReport, CantMineAnyError>> + Send;
+    /// Digs out every block in the area, one layer at a time from the top.
+    /// `on_progress` is called after each block.
+    /// Pathfinding is stopped if the future is dropped before it finishes.
+    fn quarry(
+        &self,
+        area: &Cuboid,
+        opts: &QuarryOpts,
+        on_progress: impl FnMut(&QuarryProgress) + Send,
+    ) -> impl std::future::Future<Output = QuarryProgress> + Send;
+    /// Digs a straight tunnel from where the bot is standing, with branches
+    /// if the options have them. `on_progress` is called after each step.
+    /// Pathfinding is stopped if the future is dropped before it finishes.
+    fn dig_tunnel(
+        &self,
+        opts: &TunnelOpts,
+        on_progress: impl FnMut(&TunnelReport) + Send,
+    ) -> impl std::future::Future<Output = TunnelReport> + Send;
+    /// Picks up every item that drops near any of the `origins`, like blocks
+    /// that were just broken or mobs that were just killed, whatever kind of
+    /// item it is.
+    /// Pathfinding is stopped if the future is dropped before it finishes.
+    fn collect_drops(
+        &self,
+        origins: &[Vec3],
+        opts: &DropOpts,
+    ) -> impl std::future::Future<Output = DropsReport> + Send;
+    /// Makes room if the inventory is full, by following the bot's
+    /// [`FullInventoryPolicy`]. An error means mining should stop.
+    /// `mining` is the item the blocks being mined drop, if they're all the
+    /// same, so partial stacks of anything else don't count as room.
+    fn make_room(
+        &self,
+        mining: Option<Item>,
+    ) -> impl std::future::Future<Output = Result<(), UnloadError>> + Send;
+    /// Finds blocks of the given kinds to mine, easiest to get to first.
+    fn find_mining_targets(&self, blocks: &BlockStates, search: &TargetSearch) -> Vec<BlockPos>;
+    /// Like [`Self::find_mining_targets`], but if there are none in the
+    /// loaded chunks, walks outward in a spiral until some show up.
+    /// Pathfinding is stopped if the future is dropped before it finishes.
+    fn explore_for_blocks(
+        &self,
+        blocks: &BlockStates,
+        search: &TargetSearch,
+        opts: &ExploreOpts,
+    ) -> impl std::future::Future<Output = Result<Vec<BlockPos>, ExploreError>> + Send;
+    /// What the bot has mined and picked up, added to the bot the first time
+    /// it's needed.
+    fn mining_stats(&self) -> MiningStatsTracker;
 }
 
 impl MiningExtrasClientExt for Client {
     async fn mine_block_with_best_tool(&self, pos: &BlockPos) -> Result<(), MiningError> {
         can_mine_block(pos, self.eye_position(), &self.world().read().chunks)?;
+        check_entity_blocking(self, pos)?;
+        check_hazards(self, pos)?;
 
-        self.look_at(pos.center());
-        self.mine_with_auto_tool(*pos).await;
+        let protection = self.get_component::<ToolProtection>();
+        let policy = self.get_component::<ToolPolicy>();
+        if protection.is_none() && policy.is_none() {
+            self.look_at(pos.center());
+            record_mining(self, pos, self.mine_with_auto_tool(*pos)).await;
+        } else {
+            // without protection no tool counts as worn out
+            let protection = protection.unwrap_or(ToolProtection { min_remaining: 0 });
+            equip_tool(self, pos, &protection, &policy.unwrap_or_default()).await?;
+            self.look_at(pos.center());
+            record_mining(self, pos, self.mine(*pos)).await;
+        }
 
         Ok(())
     }
@@ -68,14 +169,18 @@ impl MiningExtrasClientExt for Client {
         &self,
         blocks_pos: &[BlockPos],
     ) -> Result<(), CantMineAnyError> {
+        let mut failures = Vec::new();
         for block_pos in blocks_pos {
-            #[allow(clippy::redundant_pattern_matching)]
-            if let Ok(_) = self.mine_block_with_best_tool(block_pos).await {
-                return Ok(());
+            match self.mine_block_with_best_tool(block_pos).await {
+                Ok(()) => return Ok(()),
+                Err(err) => failures.push((*block_pos, err)),
             }
         }
 
-        Err(CantMineAnyError)
+        Err(CantMineAnyError {
+            failures,
+            stage: MineStage::InPlace,
+        })
     }
 
     async fn checked_mine(&self, pos: &BlockPos) -> Result<(), MiningError> {
@@ -83,9 +188,11 @@ impl MiningExtrasClientExt for Client {
             Ok(_) => (),
             Err(e) => return Err(e),
         }
+        check_entity_blocking(self, pos)?;
+        check_hazards(self, pos)?;
 
         self.look_at(pos.center());
-        self.mine(*pos).await;
+        record_mining(self, pos, self.mine(*pos)).await;
 
         Ok(())
     }
@@ -98,69 +205,68 @@ impl MiningExtrasClientExt for Client {
         &self,
         blocks_pos: &[BlockPos],
     ) -> Result<(), CantMineAnyError> {
-        let goal = OrGoals(
-            blocks_pos
-                .iter()
-                .map(|pos| {
-                    ReachBlockPosGoal::new_with_distance(
-                        *pos,
-                        3.2,
-                        self.world().read().chunks.clone(),
-                    )
-                })
-                .collect(),
-        );
-        self.start_goto_with_opts(goal, PathfinderOpts::new());
-        self.wait_until_goto_target_reached().await;
-
-        debug!("mining!");
-        if mine_blocks_with_best_tool_unless_already_mined(self, blocks_pos)
-            .await
-            .is_ok()
-        {
-            return Ok(());
-        }
-
-        warn!("could not mine any blocks, trying to get closer");
-
-        let goal = OrGoals(
-            blocks_pos
-                .iter()
-                .map(|pos| StandNextToBlockGoal { pos: *pos })
-                .collect(),
-        );
-        self.start_goto_with_opts(goal, PathfinderOpts::new());
-        self.wait_until_goto_target_reached().await;
-
-        debug!("mining!");
-        if mine_blocks_with_best_tool_unless_already_mined(self, blocks_pos)
-            .await
-            .is_ok()
-        {
-            return Ok(());
-        }
+        let _guard = StopPathfindingOnDrop(self);
 
-        warn!("could not mine any blocks, trying to stand in block");
+        let chunks = self.world().read().chunks.clone();
+        let mut failures = Vec::new();
+        // blocks already in reach are mined without pathfinding to them
+        for stage in [
+            MineStage::InPlace,
+            MineStage::Reach,
+            MineStage::StandNextTo,
+            MineStage::StandIn,
+        ] {
+            let walked = match stage {
+                MineStage::InPlace => Ok(()),
+                MineStage::Reach => {
+                    goto_blocks(self, blocks_pos, |pos| {
+                        ReachBlockPosGoal::new_with_distance(pos, 3.2, chunks.clone())
+                    })
+                    .await
+                }
+                MineStage::StandNextTo => {
+                    goto_blocks(self, blocks_pos, |pos| StandNextToBlockGoal { pos }).await
+                }
+                MineStage::StandIn => {
+                    goto_blocks(self, blocks_pos, |pos| StandInBlockGoal { pos }).await
+                }
+            };
 
-        let goal = OrGoals(
-            blocks_pos
-                .iter()
-                .map(|pos| StandInBlockGoal { pos: *pos })
-                .collect(),
-        );
-        self.start_goto_with_opts(goal, PathfinderOpts::new());
-        self.wait_until_goto_target_reached().await;
-
-        if mine_blocks_with_best_tool_unless_already_mined(self, blocks_pos)
-            .await
-            .is_ok()
-        {
-            return Ok(());
+            debug!("mining!");
+            match mine_blocks_with_best_tool_unless_already_mined(self, blocks_pos).await {
+                Ok(()) => return Ok(()),
+                Err(stage_failures) => {
+                    // blocks out of reach are out of reach because the bot
+                    // couldn't get to them
+                    failures = stage_failures
+                        .into_iter()
+                        .map(|(pos, err)| match (err, &walked) {
+                            (MiningError::BlockIsNotReachable, Err(walk_err)) => {
+                                (pos, walk_err.clone())
+                            }
+                            (err, _) => (pos, err),
+                        })
+                        .collect();
+                }
+            }
+            if stage != MineStage::StandIn {
+                warn!("could not mine any blocks {stage}, trying to get closer");
+            }
         }
 
         warn!("could not mine any blocks, returning");
 
-        Err(CantMineAnyError)
+        let stats = self.mining_stats();
+        for (pos, error) in &failures {
+            stats.record(MiningEvent::Failed {
+                pos: *pos,
+                error: error.clone(),
+            });
+        }
+        Err(CantMineAnyError {
+            failures,
+            stage: MineStage::StandIn,
+        })
     }
 
     async fn pick_up_item(&self, item: Item) -> Result<(), NoItemsError> {
@@ -178,7 +284,7 @@ impl MiningExtrasClientExt for Client {
                 .collect();
 
             if nearest_positions.is_empty() {
-                return Err(NoItemsError);
+                return Err(NoItemsError::NotFound(registry_name(&item)));
             }
 
             info!(
@@ -202,6 +308,8 @@ impl MiningExtrasClientExt for Client {
             Ok((nearest_items, nearest_positions))
         }
 
+        let _guard = StopPathfindingOnDrop(self);
+
         // update needs to be here too to make sure ecs is not broken
         self.wait_updates(2).await;
 
@@ -214,6 +322,7 @@ impl MiningExtrasClientExt for Client {
         self.wait_updates(2).await;
 
         let mut tick_broadcaster = self.get_tick_broadcaster();
+        let mut ticks = 0;
         loop {
             // every tick
             match tick_broadcaster.recv().await {
@@ -230,16 +339,29 @@ impl MiningExtrasClientExt for Client {
 
             // if we pick up an item, done
             let inventory_items = &self.menu().slots()[self.menu().player_slots_range()];
-            if num_items_in_slots(inventory_items, item) > starting_num_items {
+            let num_items = num_items_in_slots(inventory_items, item);
+            if num_items > starting_num_items {
                 self.stop_pathfinding();
+                self.mining_stats().record(MiningEvent::ItemsGained {
+                    item: registry_name(&item),
+                    count: num_items - starting_num_items,
+                });
                 return Ok(());
             }
 
             // if path is completed, uhh what
-            // return with warning
             if self.is_goto_target_reached() {
                 warn!("goto target reached, but no items picked up");
-                return Ok(());
+                return Err(NoItemsError::NotPickedUp {
+                    item: registry_name(&item),
+                    pos: prev_positions[0].to_block_pos_floor(),
+                });
+            }
+
+            ticks += 1;
+            if ticks > PICK_UP_TIMEOUT_TICKS {
+                warn!("took too long to pick up {item}");
+                return Err(NoItemsError::TimedOut(registry_name(&item)));
             }
 
             let nearest_items: Vec<Entity> =
@@ -250,7 +372,7 @@ impl MiningExtrasClientExt for Client {
                 .collect();
 
             if nearest_items.is_empty() {
-                return Err(NoItemsError);
+                return Err(NoItemsError::NotFound(registry_name(&item)));
             }
 
             // check if any of the items were removed
@@ -277,6 +399,366 @@ impl MiningExtrasClientExt for Client {
             }
         }
     }
+
+    async fn mine_vein(
+        &self,
+        start: &BlockPos,
+        opts: &VeinOpts,
+    ) -> Result<VeinReport, CantMineAnyError> {
+        let (kind, vein) = {
+            let world = self.world();
+            let world = world.read();
+            let chunks = &world.chunks;
+            let Some(kind) = vein_kind_at(chunks, *start) else {
+                return Err(CantMineAnyError {
+                    failures: vec![(*start, MiningError::BlockIsAir)],
+                    stage: MineStage::InPlace,
+                });
+            };
+            (
+                kind,
+                find_vein(chunks, *start, opts.diagonals, opts.max_blocks),
+            )
+        };
+        info!("mining vein of {} {kind} blocks at {start}", vein.len());
+
+        let is_vein_block =
+            |pos: &BlockPos| vein_kind_at(&self.world().read().chunks, *pos) == Some(kind);
+        let mut skipped = Vec::new();
+        let mut failures = Vec::new();
+        let mut stage = MineStage::InPlace;
+        let mut stopped = None;
+        let mut remaining = plan_route(self.position().to_block_pos_floor(), &vein);
+        loop {
+            // mined by someone else, or changed
+            remaining.retain(|pos| is_vein_block(pos));
+            if remaining.is_empty() {
+                break;
+            }
+            if let Err(err) = self.make_room(parse_registry(kind)).await {
+                warn!("stopping vein mining: {err}");
+                stopped = Some(MiningError::InventoryFull(err));
+                break;
+            }
+
+            // mine what can be reached from here first
+            let eye_position = self.eye_position();
+            let reachable = remaining.iter().position(|pos| {
+                can_mine_block(pos, eye_position, &self.world().read().chunks).is_ok()
+            });
+            if let Some(index) = reachable {
+                let pos = remaining.remove(index);
+                let result = self.mine_block_with_best_tool(&pos).await;
+                if let Err(err @ MiningError::ToolsWornOut(_)) = result {
+                    warn!("stopping vein mining: {err}");
+                    stopped = Some(err);
+                    break;
+                }
+                match result {
+                    Err(err) => failures.push((pos, err)),
+                    Ok(()) if is_vein_block(&pos) => {
+                        failures.push((pos, MiningError::BlockIsNotReachable));
+                    }
+                    Ok(()) => continue,
+                }
+                skipped.push(pos);
+                continue;
+            }
+
+            // then walk to the next block on the route
+            let Some(next) = remaining.first().copied() else {
+                break;
+            };
+            debug!("walking to next vein block at {next}");
+            let result = self.goto_and_try_mine_block(&next).await;
+            remaining.remove(0);
+            if let Some(err) = result
+                .as_ref()
+                .err()
+                .and_then(CantMineAnyError::tools_worn_out)
+            {
+                warn!("stopping vein mining: {err}");
+                stopped = Some(err);
+                break;
+            }
+            match result {
+                Err(err) => {
+                    stage = stage.max(err.stage);
+                    failures.extend(err.failures);
+                    skipped.push(next);
+                }
+                Ok(()) if is_vein_block(&next) => {
+                    failures.push((next, MiningError::BlockIsNotReachable));
+                    skipped.push(next);
+                }
+                Ok(()) => {}
+            }
+            remaining = plan_route(self.position().to_block_pos_floor(), &remaining);
+        }
+
+        let mined = vein.len() - vein.iter().filter(|pos| is_vein_block(pos)).count();
+        if mined == 0 && stopped.is_none() {
+            return Err(CantMineAnyError { failures, stage });
+        }
+
+        let mut drops = DropsReport::default();
+        if opts.collect_drops {
+            let origins: Vec<Vec3> = vein.iter().map(BlockPos::center).collect();
+            drops = self.collect_drops(&origins, &DropOpts::default()).await;
+        }
+
+        Ok(VeinReport {
+            found: vein.len(),
+            mined,
+            skipped,
+            stopped,
+            drops,
+        })
+    }
+
+    async fn quarry(
+        &self,
+        area: &Cuboid,
+        opts: &QuarryOpts,
+        mut on_progress: impl FnMut(&QuarryProgress) + Send,
+    ) -> QuarryProgress {
+        let needs_digging = |pos: &BlockPos| {
+            self.world()
+                .read()
+                .get_block_state(*pos)
+                .is_some_and(should_quarry)
+        };
+
+        let mut progress = QuarryProgress {
+            total: area
+                .layers()
+                .flat_map(|y| area.layer(y))
+                .filter(|pos| needs_digging(pos))
+                .count(),
+            ..Default::default()
+        };
+        info!(
+            "quarrying {} blocks from {} to {}",
+            progress.total, area.min, area.max
+        );
+
+        let mut skipped = HashSet::new();
+        for y in area.layers() {
+            progress.layer = y;
+            let mut mined = Vec::new();
+            loop {
+                let targets: Vec<BlockPos> = area
+                    .layer(y)
+                    .into_iter()
+                    .filter(|pos| !skipped.contains(pos) && needs_digging(pos))
+                    .collect();
+                if targets.is_empty() {
+                    break;
+                }
+                if let Err(err) = self.make_room(None).await {
+                    warn!("stopping quarry: {err}");
+                    progress.stopped = Some(MiningError::InventoryFull(err));
+                    return progress;
+                }
+
+                // mine what can be reached from here first, then walk to the
+                // closest block left
+                let eye_position = self.eye_position();
+                let reachable = targets.iter().find(|pos| {
+                    can_mine_block(pos, eye_position, &self.world().read().chunks).is_ok()
+                });
+                let pos = match reachable {
+                    Some(pos) => {
+                        if let Err(err @ MiningError::ToolsWornOut(_)) =
+                            self.mine_block_with_best_tool(pos).await
+                        {
+                            warn!("stopping quarry: {err}");
+                            progress.stopped = Some(err);
+                            return progress;
+                        }
+                        *pos
+                    }
+                    None => {
+                        let bot_pos = self.position().to_block_pos_floor();
+                        let next = *targets
+                            .iter()
+                            .min_by_key(|pos| pos.distance_squared_to(bot_pos))
+                            .expect("targets is not empty");
+                        let result = self.goto_and_try_mine_block(&next).await;
+                        if let Some(err) = result.err().and_then(|err| err.tools_worn_out()) {
+                            warn!("stopping quarry: {err}");
+                            progress.stopped = Some(err);
+                            return progress;
+                        }
+                        next
+                    }
+                };
+
+                if needs_digging(&pos) {
+                    debug!("could not quarry block at {pos}, skipping it");
+                    skipped.insert(pos);
+                    progress.skipped += 1;
+                } else {
+                    progress.mined += 1;
+                    mined.push(pos.center());
+                }
+                on_progress(&progress);
+            }
+
+            if opts.collect_drops && !mined.is_empty() {
+                let drops = self.collect_drops(&mined, &DropOpts::default()).await;
+                progress.drops.merge(&drops);
+            }
+        }
+
+        progress
+    }
+
+    async fn dig_tunnel(
+        &self,
+        opts: &TunnelOpts,
+        on_progress: impl FnMut(&TunnelReport) + Send,
+    ) -> TunnelReport {
+        tunnel::dig_tunnel(self, opts, on_progress).await
+    }
+
+    async fn collect_drops(&self, origins: &[Vec3], opts: &DropOpts) -> DropsReport {
+        drops::collect_drops(self, origins, opts).await
+    }
+
+    async fn make_room(&self, mining: Option<Item>) -> Result<(), UnloadError> {
+        unload::make_room(self, mining).await
+    }
+
+    fn find_mining_targets(&self, blocks: &BlockStates, search: &TargetSearch) -> Vec<BlockPos> {
+        let position = self.position();
+        let eye_position = self.eye_position();
+        let world = self.world();
+        let world = world.read();
+        // nearest first, so the search can stop at the first one too far away
+        let candidates: Vec<BlockPos> = world
+            .find_blocks(position, blocks)
+            .take_while(|pos| pos.center().distance_squared_to(position) <= search.radius.powi(2))
+            .take(search.candidates)
+            .collect();
+        rank_targets(
+            &world.chunks,
+            position.to_block_pos_floor(),
+            eye_position,
+            &candidates,
+        )
+    }
+
+    async fn explore_for_blocks(
+        &self,
+        blocks: &BlockStates,
+        search: &TargetSearch,
+        opts: &ExploreOpts,
+    ) -> Result<Vec<BlockPos>, ExploreError> {
+        explore::explore_for_blocks(self, blocks, search, opts).await
+    }
+
+    fn mining_stats(&self) -> MiningStatsTracker {
+        if let Some(tracker) = self.get_component::<MiningStatsTracker>() {
+            return tracker;
+        }
+        let tracker = MiningStatsTracker::default();
+        self.ecs
+            .lock()
+            .entity_mut(self.entity)
+            .insert(tracker.clone());
+        tracker
+    }
+}
+
+/// Options for [`MiningExtrasClientExt::mine_vein`].
+#[derive(Debug, Clone)]
+pub struct VeinOpts {
+    /// Whether blocks touching only at an edge or corner are part of the vein.
+    pub diagonals: bool,
+    /// The most blocks to mine, so a huge vein or a forest doesn't take
+    /// forever.
+    pub max_blocks: usize,
+    /// Whether to pick up the items that dropped after mining.
+    pub collect_drops: bool,
+}
+
+impl Default for VeinOpts {
+    fn default() -> Self {
+        Self {
+            diagonals: false,
+            max_blocks: 64,
+            collect_drops: true,
+        }
+    }
+}
+
+/// What happened to a vein.
+#[derive(Debug, Clone, Default)]
+pub struct VeinReport {
+    /// How many blocks the vein had.
+    pub found: usize,
+    pub mined: usize,
+    /// Blocks that couldn't be reached or mined.
+    pub skipped: Vec<BlockPos>,
+    /// Why it ended early, if it did.
+    pub stopped: Option<MiningError>,
+    /// What was picked up after.
+    pub drops: DropsReport,
+}
+
+/// Holds the best tool for the block that isn't nearly broken, with the
+/// enchantments the policy prefers, moving it to the hotbar if it's somewhere
+/// else in the inventory.
+async fn equip_tool(
+    bot: &Client,
+    pos: &BlockPos,
+    protection: &ToolProtection,
+    policy: &ToolPolicy,
+) -> Result<(), MiningError> {
+    let block_state = bot.world().read().get_block_state(*pos).unwrap_or_default();
+    let inventory = bot.component::<Inventory>();
+    let Some(choice) =
+        best_tool_for_block(block_state, &inventory.inventory_menu, protection, policy)
+    else {
+        let block: Box<dyn BlockTrait> = block_state.into();
+        return Err(MiningError::ToolsWornOut(block.id().to_owned()));
+    };
+
+    let hotbar_slot = match choice {
+        ToolChoice::Hotbar(slot) => slot as u8,
+        ToolChoice::Inventory(slot) => {
+            let target_slot = inventory.selected_hotbar_slot;
+            debug!("swapping backup tool from slot {slot} into hotbar slot {target_slot}");
+            bot.ecs.lock().trigger(ContainerClickEvent {
+                entity: bot.entity,
+                window_id: inventory.id,
+                operation: ClickOperation::Swap(SwapClick {
+                    source_slot: slot as u16,
+                    target_slot,
+                }),
+            });
+            bot.wait_ticks(1).await;
+            target_slot
+        }
+    };
+    if inventory.selected_hotbar_slot != hotbar_slot {
+        bot.ecs.lock().trigger(SetSelectedHotbarSlotEvent {
+            entity: bot.entity,
+            slot: hotbar_slot,
+        });
+        bot.wait_ticks(1).await;
+    }
+    Ok(())
+}
+
+/// Stops pathfinding when dropped, so the bot doesn't keep walking after a
+/// future that is moving it gets cancelled.
+pub(crate) struct StopPathfindingOnDrop<'a>(pub(crate) &'a Client);
+impl Drop for StopPathfindingOnDrop<'_> {
+    fn drop(&mut self) {
+        self.0.stop_pathfinding();
+    }
 }
 
 /// Checks whether the block at the given position can be mined.
@@ -290,8 +772,7 @@ pub fn can_mine_block(
     if block_state.is_air() {
         return Err(MiningError::BlockIsAir);
     }
-    let block: Box<dyn BlockTrait> = block_state.into();
-    if (*block).behavior().destroy_time < -1. {
+    if !is_breakable(block_state) {
         return Err(MiningError::BlockIsNotBreakable);
     }
 
@@ -313,10 +794,25 @@ pub fn can_mine_block(
     Ok(())
 }
 
+/// Whether a block can be broken in survival. Bedrock and the like have a
+/// negative destroy time.
+pub fn is_breakable(block_state: BlockState) -> bool {
+    let block: Box<dyn BlockTrait> = block_state.into();
+    block.behavior().destroy_time >= 0.
+}
+
+/// Whether a block is water or lava, which can't be mined.
+pub fn is_fluid(block_state: BlockState) -> bool {
+    let block: Box<dyn BlockTrait> = block_state.into();
+    matches!(block.id(), "water" | "lava" | "bubble_column")
+}
+
+/// Mines the first of the blocks it can, or gives why each one couldn't be.
 async fn mine_blocks_with_best_tool_unless_already_mined(
     bot: &Client,
     blocks_pos: &[BlockPos],
-) -> Result<(), CantMineAnyError> {
+) -> Result<(), Vec<(BlockPos, MiningError)>> {
+    let mut failures = Vec::new();
     for block_pos in blocks_pos {
         let block_state = bot
             .world()
@@ -332,6 +828,7 @@ async fn mine_blocks_with_best_tool_unless_already_mined(
 
             // block was probably mined by someone else
             warn!("block {} is already mined", block_pos);
+            failures.push((*block_pos, MiningError::BlockIsAir));
             continue;
         }
 
@@ -339,16 +836,168 @@ async fn mine_blocks_with_best_tool_unless_already_mined(
             Ok(_) => {
                 return Ok(());
             }
-            Err(_) => {
-                continue;
+            Err(err) => {
+                debug!("could not mine {block_pos}: {err}");
+                failures.push((*block_pos, err));
             }
         }
     }
 
-    Err(CantMineAnyError)
+    Err(failures)
+}
+
+/// Runs `mine` and records how long it took, the tool durability it used and
+/// the block if it was broken.
+async fn record_mining(bot: &Client, pos: &BlockPos, mine: impl Future<Output = ()> + Send) {
+    let block_id = bot.world().read().get_block_state(*pos).map(|block_state| {
+        let block: Box<dyn BlockTrait> = block_state.into();
+        block.id()
+    });
+    let damage_before = inventory_damage(bot);
+    let started = Instant::now();
+
+    mine.await;
+
+    let stats = bot.mining_stats();
+    stats.record(MiningEvent::Digging(started.elapsed()));
+    // tools that broke take their damage with them, so this can go down
+    let used = inventory_damage(bot) - damage_before;
+    if used > 0 {
+        stats.record(MiningEvent::DurabilityUsed(used as u32));
+    }
+    let broken = bot
+        .world()
+        .read()
+        .get_block_state(*pos)
+        .is_some_and(|block_state| block_state.is_air());
+    if let (Some(block), true) = (block_id, broken) {
+        stats.record(MiningEvent::BlockBroken {
+            pos: *pos,
+            block: block.to_owned(),
+        });
+    }
+}
+
+fn inventory_damage(bot: &Client) -> i32 {
+    let menu = bot.menu();
+    total_damage(&menu.slots()[menu.player_slots_range()])
+}
+
+/// Walks to where any of the goals made for the blocks is met.
+pub(crate) async fn goto_blocks<G>(
+    bot: &Client,
+    blocks_pos: &[BlockPos],
+    make_goal: impl Fn(BlockPos) -> G,
+) -> Result<(), MiningError>
+where
+    G: Goal + Debug + Send + Sync + 'static,
+{
+    let goal = OrGoals(blocks_pos.iter().map(|pos| make_goal(*pos)).collect());
+    bot.start_goto_with_opts(goal, PathfinderOpts::new());
+    let started = Instant::now();
+    let timed_out = timeout(GOTO_TIMEOUT, bot.wait_until_goto_target_reached())
+        .await
+        .is_err();
+    bot.mining_stats()
+        .record(MiningEvent::Pathing(started.elapsed()));
+    if timed_out {
+        bot.stop_pathfinding();
+        return Err(MiningError::TimedOut);
+    }
+
+    let feet = bot.position().to_block_pos_floor();
+    if !blocks_pos.iter().any(|pos| make_goal(*pos).success(feet)) {
+        return Err(MiningError::PathFailed);
+    }
+    Ok(())
+}
+
+/// Checks whether an entity is between the bot's eyes and the block, since
+/// the entity would get hit instead.
+fn check_entity_blocking(bot: &Client, pos: &BlockPos) -> Result<(), MiningError> {
+    let instance_name = bot.component::<InstanceName>();
+    let eye_position = bot.eye_position();
+    let target = pos.center();
+
+    let mut query = bot.ecs.lock().query_filtered::<(
+        Entity,
+        &EntityKind,
+        &Position,
+        &EntityDimensions,
+        &InstanceName,
+    ), Without<Dead>>();
+    let ecs = bot.ecs.lock();
+    let blocking =
+        query
+            .iter(&ecs)
+            .find(|(entity, kind, position, dimensions, entity_instance)| {
+                if *entity == bot.entity
+                    || *entity_instance != &instance_name
+                    || !can_be_hit(**kind)
+                {
+                    return false;
+                }
+                let half_width = f64::from(dimensions.width) / 2.;
+                let min = ***position - Vec3::new(half_width, 0., half_width);
+                let max =
+                    ***position + Vec3::new(half_width, f64::from(dimensions.height), half_width);
+                segment_hits_box(eye_position, target, min, max)
+            });
+    match blocking {
+        Some((_, kind, ..)) => Err(MiningError::EntityBlocking(registry_name(kind))),
+        None => Ok(()),
+    }
+}
+
+/// Whether an entity gets in the way of hitting blocks behind it. Items,
+/// experience orbs and projectiles don't.
+fn can_be_hit(kind: EntityKind) -> bool {
+    !matches!(
+        kind,
+        EntityKind::Item
+            | EntityKind::ExperienceOrb
+            | EntityKind::Arrow
+            | EntityKind::SpectralArrow
+            | EntityKind::Trident
+            | EntityKind::Snowball
+            | EntityKind::Egg
+            | EntityKind::EnderPearl
+            | EntityKind::FireworkRocket
+            | EntityKind::AreaEffectCloud
+            | EntityKind::Marker
+            | EntityKind::ItemDisplay
+            | EntityKind::BlockDisplay
+            | EntityKind::TextDisplay
+    )
+}
+
+/// Whether the line between two points passes through a box.
+fn segment_hits_box(from: Vec3, to: Vec3, min: Vec3, max: Vec3) -> bool {
+    let mut enter: f64 = 0.;
+    let mut exit: f64 = 1.;
+    for (start, end, low, high) in [
+        (from.x, to.x, min.x, max.x),
+        (from.y, to.y, min.y, max.y),
+        (from.z, to.z, min.z, max.z),
+    ] {
+        let delta = end - start;
+        if delta == 0. {
+            if start < low || start > high {
+                return false;
+            }
+            continue;
+        }
+        let (t1, t2) = ((low - start) / delta, (high - start) / delta);
+        enter = enter.max(t1.min(t2));
+        exit = exit.min(t1.max(t2));
+        if enter > exit {
+            return false;
+        }
+    }
+    true
 }
 
-#[derive(Debug, Error)]
+#[derive(Debug, Clone, Error)]
 pub enum MiningError {
     #[error("Block is air")]
     BlockIsAir,
@@ -356,24 +1005,116 @@ pub enum MiningError {
     BlockIsNotBreakable,
     #[error("Block is not reachable")]
     BlockIsNotReachable,
-    #[error("there is an entity blocking the block")]
-    EntityBlocking,
+    #[error("A {0} is in the way")]
+    EntityBlocking(String),
+    #[error("Could not find a path to it")]
+    PathFailed,
+    #[error("Took too long to get to it")]
+    TimedOut,
+    #[error("Breaking it would let in lava at {0}")]
+    ExposesLava(BlockPos),
+    #[error("Breaking it would let in water at {0}")]
+    ExposesWater(BlockPos),
+    #[error("Breaking it would drop the block at {0} on the bot")]
+    FallingBlockAbove(BlockPos),
+    #[error("The bot is standing on it over a long fall")]
+    LongFall,
+    #[error("Breaking it would open into a cave with a {0} in view")]
+    HostileInView(String),
+    #[error("Every tool that can mine {0} is nearly broken")]
+    ToolsWornOut(String),
+    #[error("{0}")]
+    InventoryFull(UnloadError),
+}
+
+/// How far [`MiningExtrasClientExt::goto_and_try_mine_blocks`] got before
+/// giving up, from mining where the bot stood to standing in the blocks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum MineStage {
+    /// Mining from where the bot was, without moving.
+    InPlace,
+    /// Walking to within reach of the blocks.
+    Reach,
+    /// Walking right next to the blocks.
+    StandNextTo,
+    /// Walking into the blocks, for when they're under the bot.
+    StandIn,
 }
 
-#[derive(Debug)]
-pub struct CantMineAnyError;
+impl Display for MineStage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InPlace => write!(f, "without moving"),
+            Self::Reach => write!(f, "after getting in reach"),
+            Self::StandNextTo => write!(f, "after standing next to them"),
+            Self::StandIn => write!(f, "after standing in them"),
+        }
+    }
+}
+
+/// None of the blocks could be mined.
+#[derive(Debug, Clone)]
+pub struct CantMineAnyError {
+    /// Why each block couldn't be mined, at the last stage tried.
+    pub failures: Vec<(BlockPos, MiningError)>,
+    /// The last stage tried.
+    pub stage: MineStage,
+}
 impl Display for CantMineAnyError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "Cant mine any blocks requested")
+        write!(f, "Cant mine any blocks requested {}", self.stage)?;
+        for (i, (pos, err)) in self.failures.iter().take(MAX_LISTED_FAILURES).enumerate() {
+            let separator = if i == 0 { ":" } else { ";" };
+            write!(f, "{separator} {} {} {} {err}", pos.x, pos.y, pos.z)?;
+        }
+        if self.failures.len() > MAX_LISTED_FAILURES {
+            write!(
+                f,
+                "; and {} more",
+                self.failures.len() - MAX_LISTED_FAILURES
+            )?;
+        }
+        Ok(())
     }
 }
 impl Error for CantMineAnyError {}
 
-#[derive(Debug)]
-pub struct NoItemsError;
-impl Display for NoItemsError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "No items found")
+impl CantMineAnyError {
+    /// The failure from running out of usable tools, if there was one. Other
+    /// blocks won't go any better, so callers should stop.
+    pub fn tools_worn_out(&self) -> Option<MiningError> {
+        self.failures
+            .iter()
+            .find(|(_, err)| matches!(err, MiningError::ToolsWornOut(_)))
+            .map(|(_, err)| err.clone())
+    }
+}
+
+#[derive(Debug, Clone, Error)]
+pub enum NoItemsError {
+    #[error("No {0} found nearby")]
+    NotFound(String),
+    #[error("Got to the {item} at {pos} but didn't pick it up")]
+    NotPickedUp { item: String, pos: BlockPos },
+    #[error("Took too long to pick up {0}")]
+    TimedOut(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use azalea::Vec3;
+
+    use super::segment_hits_box;
+
+    #[test]
+    fn test_segment_hits_box() {
+        let min = Vec3::new(0., 0., 0.);
+        let max = Vec3::new(1., 2., 1.);
+        let hits = |from, to| segment_hits_box(from, to, min, max);
+        assert!(hits(Vec3::new(-1., 1., 0.5), Vec3::new(2., 1., 0.5)));
+        assert!(!hits(Vec3::new(-1., 3., 0.5), Vec3::new(2., 3., 0.5)));
+        // stops before reaching the box
+        assert!(!hits(Vec3::new(-3., 1., 0.5), Vec3::new(-1., 1., 0.5)));
+        assert!(hits(Vec3::new(-1., -1., -1.), Vec3::new(2., 3., 2.)));
     }
 }
-impl Error for NoItemsError {}
@@ -17,6 +17,9 @@ pub trait NearestEntityClientExt {
         item: Item,
         max_distance: f64,
     ) -> impl Iterator<Item = Entity>;
+    /// The kinds of the items on the ground within a certain distance, closest
+    /// first.
+    fn nearby_item_kinds(&self, max_distance: f64) -> Vec<Item>;
 }
 
 impl NearestEntityClientExt for Client {
@@ -93,4 +96,35 @@ impl NearestEntityClientExt for Client {
 
         entities.into_iter().map(|(entity, _)| entity)
     }
+
+    fn nearby_item_kinds(&self, max_distance: f64) -> Vec<Item> {
+        let client_instance_name = self.component::<InstanceName>();
+        let client_position = self.eye_position();
+
+        let mut item_query = self
+            .ecs
+            .lock()
+            .query::<(&ItemItem, &Position, &InstanceName)>();
+
+        let mut items: Vec<_> = item_query
+            .iter(&self.ecs.lock())
+            .filter(|(_, _, instance_name)| *instance_name == &client_instance_name)
+            .map(|(item_component, position, _)| {
+                (
+                    item_component.kind(),
+                    client_position.distance_squared_to(**position),
+                )
+            })
+            .filter(|(_, distance_sq)| *distance_sq <= max_distance * max_distance)
+            .collect();
+        items.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal));
+
+        let mut kinds = Vec::new();
+        for (kind, _) in items {
+            if !kinds.contains(&kind) {
+                kinds.push(kind);
+            }
+        }
+        kinds
+    }
 }
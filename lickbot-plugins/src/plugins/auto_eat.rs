@@ -10,20 +10,21 @@ use azalea::{
     entity::{LocalEntity, metadata::Player},
     interact::StartUseItemEvent,
     inventory::{
-        ContainerClickEvent, Inventory, InventorySet, SetSelectedHotbarSlotEvent,
+        self, ContainerClickEvent, Inventory, SetSelectedHotbarSlotEvent,
         operations::{ClickOperation, SwapClick},
     },
     local_player::Hunger,
     mining::continue_mining_block,
     packet::game::handle_outgoing_packets,
-    physics::PhysicsSet,
+    physics::PhysicsSystems,
     prelude::*,
     protocol::packets::game::s_interact::InteractionHand,
     registry::Item,
 };
+use serde::{Deserialize, Serialize};
 use tracing::{debug, trace};
 
-use crate::plugins::kill_aura::AutoKill;
+use crate::plugins::kill_aura::{self, AutoKill};
 
 /// Automatically eat food to avoid starving to death
 pub struct AutoEatPlugin;
@@ -33,26 +34,41 @@ impl Plugin for AutoEatPlugin {
         app.add_systems(
             GameTick,
             handle_auto_eat
-                .after(crate::plugins::kill_aura::handle_auto_kill)
+                .after(kill_aura::handle_auto_kill)
                 .before(handle_outgoing_packets)
                 .before(continue_mining_block)
-                .before(InventorySet)
-                .before(PhysicsSet),
+                .before(inventory::handle_container_click_event)
+                .before(PhysicsSystems),
         );
     }
 }
 
+/// Component present when autoeat is enabled.
+#[derive(Component, Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AutoEat {
+    /// Food level below which the bot eats, out of 20.
+    pub hunger_threshold: u32,
+}
+
+impl Default for AutoEat {
+    fn default() -> Self {
+        Self {
+            hunger_threshold: 18,
+        }
+    }
+}
+
 #[allow(clippy::type_complexity)]
 pub fn handle_auto_eat(
-    mut query: Query<
-        (Entity, &Hunger, &Inventory, Option<&AutoKill>),
+    query: Query<
+        (Entity, &AutoEat, &Hunger, &Inventory, Option<&AutoKill>),
         (With<Player>, With<LocalEntity>),
     >,
-    mut start_use_item_events: EventWriter<StartUseItemEvent>,
-    mut container_click_events: EventWriter<ContainerClickEvent>,
-    mut set_selected_hotbar_slot_events: EventWriter<SetSelectedHotbarSlotEvent>,
+    mut commands: Commands,
+    mut start_use_item_events: MessageWriter<StartUseItemEvent>,
 ) {
-    for (entity, hunger, inventory, auto_kill) in &mut query {
+    for (entity, auto_eat, hunger, inventory, auto_kill) in &query {
         // dont eat if killing
         if let Some(auto_kill) = auto_kill
             && auto_kill.is_attacking
@@ -60,7 +76,7 @@ pub fn handle_auto_eat(
             continue;
         }
 
-        if hunger.food >= 18 {
+        if hunger.food >= auto_eat.hunger_threshold {
             continue;
         }
 
@@ -91,7 +107,7 @@ pub fn handle_auto_eat(
             // slot num is 0 indexed
             debug!("Swapping Food from {best_slot} and selecting slot {}", 9);
 
-            container_click_events.write(ContainerClickEvent {
+            commands.trigger(ContainerClickEvent {
                 entity,
                 window_id: inventory.id,
                 operation: ClickOperation::Swap(SwapClick {
@@ -101,8 +117,7 @@ pub fn handle_auto_eat(
             });
 
             if inventory.selected_hotbar_slot != 8 {
-                set_selected_hotbar_slot_events
-                    .write(SetSelectedHotbarSlotEvent { entity, slot: 8 });
+                commands.trigger(SetSelectedHotbarSlotEvent { entity, slot: 8 });
             }
         }
 
@@ -7,6 +7,7 @@ use azalea::entity::{LocalEntity, Position};
 use azalea::nearest_entity::EntityFinder;
 use azalea::physics::PhysicsSystems;
 use azalea::{Vec3, prelude::*};
+use serde::{Deserialize, Serialize};
 
 use crate::plugins;
 
@@ -25,7 +26,7 @@ impl Plugin for AutoLookPlugin {
 }
 
 /// Component present when autolook is enabled
-#[derive(Component, Clone)]
+#[derive(Component, Clone, Default, Serialize, Deserialize)]
 pub struct AutoLook;
 
 #[allow(clippy::type_complexity)]
@@ -10,6 +10,7 @@ use azalea::inventory::operations::{ClickOperation, SwapClick};
 use azalea::inventory::{self, ContainerClickEvent, Inventory, ItemStack, Menu};
 use azalea::prelude::*;
 use azalea::registry::Item;
+use serde::{Deserialize, Serialize};
 use tracing::{debug, error};
 
 /// Plugin which automatically switches totem to offhand.
@@ -25,7 +26,7 @@ impl Plugin for AutoTotemPlugin {
 }
 
 /// Component present when autototem is enabled.
-#[derive(Component)]
+#[derive(Component, Clone, Default, Serialize, Deserialize)]
 pub struct AutoTotem;
 
 #[allow(clippy::type_complexity)]
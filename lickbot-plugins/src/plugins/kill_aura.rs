@@ -16,6 +16,7 @@ use azalea::pathfinder::Pathfinder;
 use azalea::physics::PhysicsSystems;
 use azalea::world::MinecraftEntityId;
 use azalea::{Vec3, prelude::*};
+use serde::{Deserialize, Serialize};
 use tracing::{debug, error, trace};
 
 use crate::entity_target::{EntityTarget, EntityTargets, TargetFinder};
@@ -39,7 +40,8 @@ impl Plugin for AutoKillPlugin {
 }
 
 /// Component present when auto kill is enabled.
-#[derive(Component, Clone)]
+#[derive(Component, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct AutoKill {
     /// if true, will switch to the best weapon in hotbar
     pub switch_weapon: bool,
@@ -50,6 +52,7 @@ pub struct AutoKill {
     pub targets: EntityTargets,
 
     /// whether currently attacking a target
+    #[serde(skip)]
     pub is_attacking: bool,
 }
 
@@ -1,3 +1,5 @@
+pub mod auto_eat;
 pub mod auto_look;
 pub mod auto_totem;
+pub mod kill_aura;
 pub mod look_when_mining;
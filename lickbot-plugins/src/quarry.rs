@@ -0,0 +1,131 @@
+//! Digging out a box of blocks, one layer at a time from the top.
+
+use azalea::BlockPos;
+use azalea::blocks::BlockState;
+
+use crate::drops::DropsReport;
+use crate::mining::{MiningError, is_breakable, is_fluid};
+
+/// A box of blocks between two corners, including both.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cuboid {
+    pub min: BlockPos,
+    pub max: BlockPos,
+}
+
+impl Cuboid {
+    /// Makes a cuboid from any two opposite corners.
+    pub fn new(a: BlockPos, b: BlockPos) -> Self {
+        Self {
+            min: BlockPos::new(a.x.min(b.x), a.y.min(b.y), a.z.min(b.z)),
+            max: BlockPos::new(a.x.max(b.x), a.y.max(b.y), a.z.max(b.z)),
+        }
+    }
+
+    /// How many blocks are in the cuboid, or `None` if it's too big to count.
+    pub fn volume(&self) -> Option<u64> {
+        let length = |min: i32, max: i32| (i64::from(max) - i64::from(min) + 1) as u64;
+        length(self.min.x, self.max.x)
+            .checked_mul(length(self.min.y, self.max.y))?
+            .checked_mul(length(self.min.z, self.max.z))
+    }
+
+    /// The y levels from the top down.
+    pub fn layers(&self) -> impl Iterator<Item = i32> + use<> {
+        (self.min.y..=self.max.y).rev()
+    }
+
+    /// The blocks of a layer row by row, going back and forth so the bot
+    /// doesn't have to walk back to the start of each row.
+    pub fn layer(&self, y: i32) -> Vec<BlockPos> {
+        let mut blocks = Vec::new();
+        for (row, x) in (self.min.x..=self.max.x).enumerate() {
+            let zs: Box<dyn Iterator<Item = i32>> = if row % 2 == 0 {
+                Box::new(self.min.z..=self.max.z)
+            } else {
+                Box::new((self.min.z..=self.max.z).rev())
+            };
+            blocks.extend(zs.map(|z| BlockPos::new(x, y, z)));
+        }
+        blocks
+    }
+}
+
+/// Whether a block should be dug out. Air, fluids and unbreakable blocks are
+/// left alone.
+pub fn should_quarry(block_state: BlockState) -> bool {
+    !block_state.is_air() && is_breakable(block_state) && !is_fluid(block_state)
+}
+
+/// Options for [`MiningExtrasClientExt::quarry`].
+///
+/// [`MiningExtrasClientExt::quarry`]: crate::mining::MiningExtrasClientExt::quarry
+#[derive(Debug, Clone)]
+pub struct QuarryOpts {
+    /// Whether to pick up the items that dropped after each layer.
+    pub collect_drops: bool,
+}
+
+impl Default for QuarryOpts {
+    fn default() -> Self {
+        Self {
+            collect_drops: true,
+        }
+    }
+}
+
+/// How far a quarry has got.
+#[derive(Debug, Clone, Default)]
+pub struct QuarryProgress {
+    /// How many blocks needed digging when it started.
+    pub total: usize,
+    pub mined: usize,
+    /// Blocks that couldn't be reached or mined.
+    pub skipped: usize,
+    /// The y level being dug.
+    pub layer: i32,
+    /// Why it ended early, if it did.
+    pub stopped: Option<MiningError>,
+    /// What was picked up so far.
+    pub drops: DropsReport,
+}
+
+impl QuarryProgress {
+    /// How much is done, out of 100. Blocks falling in can push the count
+    /// past the total, so it stops at 100.
+    pub fn percent(&self) -> u32 {
+        if self.total == 0 {
+            return 100;
+        }
+        ((self.mined + self.skipped) * 100 / self.total).min(100) as u32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use azalea::BlockPos;
+
+    use super::Cuboid;
+
+    #[test]
+    fn test_cuboid() {
+        let cuboid = Cuboid::new(BlockPos::new(1, 5, 1), BlockPos::new(0, 4, 0));
+        assert_eq!(cuboid.min, BlockPos::new(0, 4, 0));
+        assert_eq!(cuboid.volume(), Some(8));
+        let huge = Cuboid::new(
+            BlockPos::new(i32::MIN, i32::MIN, i32::MIN),
+            BlockPos::new(i32::MAX, i32::MAX, i32::MAX),
+        );
+        assert_eq!(huge.volume(), None);
+        assert_eq!(cuboid.layers().collect::<Vec<_>>(), vec![5, 4]);
+        assert_eq!(
+            cuboid.layer(5),
+            vec![
+                BlockPos::new(0, 5, 0),
+                BlockPos::new(0, 5, 1),
+                BlockPos::new(1, 5, 1),
+                BlockPos::new(1, 5, 0),
+            ]
+        );
+    }
+}
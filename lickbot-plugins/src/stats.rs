@@ -0,0 +1,259 @@
+//! Counting what the bot gets done while mining, to tell whether a run is
+//! worth it.
+
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::sync::Arc;
+use std::time::Duration;
+
+use azalea::BlockPos;
+use azalea::ecs::prelude::*;
+use parking_lot::Mutex;
+use tokio::sync::broadcast;
+use tracing::debug;
+
+use crate::mining::MiningError;
+
+/// Something that happened while mining.
+#[derive(Debug, Clone)]
+pub enum MiningEvent {
+    BlockBroken { pos: BlockPos, block: String },
+    ItemsGained { item: String, count: i32 },
+    DurabilityUsed(u32),
+    Pathing(Duration),
+    Digging(Duration),
+    Failed { pos: BlockPos, error: MiningError },
+}
+
+/// Totals of [`MiningEvent`]s.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MiningStats {
+    pub blocks_broken: BTreeMap<String, u32>,
+    pub items_gained: BTreeMap<String, i32>,
+    pub durability_used: u32,
+    pub pathing: Duration,
+    pub digging: Duration,
+    /// Failures by reason.
+    pub failures: BTreeMap<String, u32>,
+}
+
+impl MiningStats {
+    pub fn record(&mut self, event: &MiningEvent) {
+        match event {
+            MiningEvent::BlockBroken { block, .. } => {
+                *self.blocks_broken.entry(block.clone()).or_default() += 1;
+            }
+            MiningEvent::ItemsGained { item, count } => {
+                *self.items_gained.entry(item.clone()).or_default() += count;
+            }
+            MiningEvent::DurabilityUsed(used) => self.durability_used += used,
+            MiningEvent::Pathing(time) => self.pathing += *time,
+            MiningEvent::Digging(time) => self.digging += *time,
+            MiningEvent::Failed { error, .. } => {
+                *self.failures.entry(failure_reason(error)).or_default() += 1;
+            }
+        }
+    }
+
+    /// What was added to these stats since `earlier` was taken. Nothing goes
+    /// below zero, in case the stats were reset in between.
+    pub fn since(&self, earlier: &MiningStats) -> MiningStats {
+        fn subtract<T>(
+            now: &BTreeMap<String, T>,
+            earlier: &BTreeMap<String, T>,
+        ) -> BTreeMap<String, T>
+        where
+            T: Copy + Default + Ord + std::ops::Sub<Output = T>,
+        {
+            now.iter()
+                .map(|(key, value)| {
+                    let before = earlier.get(key).copied().unwrap_or_default();
+                    let added = if *value > before {
+                        *value - before
+                    } else {
+                        T::default()
+                    };
+                    (key.clone(), added)
+                })
+                .filter(|(_, value)| *value != T::default())
+                .collect()
+        }
+
+        MiningStats {
+            blocks_broken: subtract(&self.blocks_broken, &earlier.blocks_broken),
+            items_gained: subtract(&self.items_gained, &earlier.items_gained),
+            durability_used: self.durability_used.saturating_sub(earlier.durability_used),
+            pathing: self.pathing.saturating_sub(earlier.pathing),
+            digging: self.digging.saturating_sub(earlier.digging),
+            failures: subtract(&self.failures, &earlier.failures),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == MiningStats::default()
+    }
+
+    pub fn total_blocks_broken(&self) -> u32 {
+        self.blocks_broken.values().sum()
+    }
+
+    pub fn total_failures(&self) -> u32 {
+        self.failures.values().sum()
+    }
+}
+
+impl Display for MiningStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "broke {} blocks", self.total_blocks_broken())?;
+        if !self.blocks_broken.is_empty() {
+            write!(f, " ({})", format_counts(&self.blocks_broken))?;
+        }
+        if !self.items_gained.is_empty() {
+            write!(f, ", gained {}", format_counts(&self.items_gained))?;
+        }
+        write!(
+            f,
+            ", used {} durability, {}s pathing, {}s digging",
+            self.durability_used,
+            self.pathing.as_secs(),
+            self.digging.as_secs()
+        )?;
+        if !self.failures.is_empty() {
+            write!(
+                f,
+                ", {} failures ({})",
+                self.total_failures(),
+                format_counts(&self.failures)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+fn format_counts<T: Display>(counts: &BTreeMap<String, T>) -> String {
+    counts
+        .iter()
+        .map(|(name, count)| format!("{count} {name}"))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// The kind of a failure, without the details that would keep the same kind
+/// of failure from being counted together.
+fn failure_reason(error: &MiningError) -> String {
+    match error {
+        MiningError::BlockIsAir => "air",
+        MiningError::BlockIsNotBreakable => "unbreakable",
+        MiningError::BlockIsNotReachable => "unreachable",
+        MiningError::EntityBlocking(_) => "entity in the way",
+        MiningError::PathFailed => "no path",
+        MiningError::TimedOut => "timed out",
+        MiningError::ExposesLava(_) => "lava",
+        MiningError::ExposesWater(_) => "water",
+        MiningError::FallingBlockAbove(_) => "falling block",
+        MiningError::LongFall => "long fall",
+        MiningError::HostileInView(_) => "hostile mob",
+        MiningError::ToolsWornOut(_) => "tools worn out",
+        MiningError::InventoryFull(_) => "inventory full",
+    }
+    .to_owned()
+}
+
+/// Keeps a bot's [`MiningStats`] and sends out every [`MiningEvent`] as it
+/// happens. Clones share the same stats.
+#[derive(Component, Clone, Debug)]
+pub struct MiningStatsTracker {
+    stats: Arc<Mutex<MiningStats>>,
+    events: broadcast::Sender<MiningEvent>,
+}
+
+impl Default for MiningStatsTracker {
+    fn default() -> Self {
+        Self {
+            stats: Arc::default(),
+            events: broadcast::channel(64).0,
+        }
+    }
+}
+
+impl MiningStatsTracker {
+    pub fn record(&self, event: MiningEvent) {
+        debug!("mining event: {event:?}");
+        self.stats.lock().record(&event);
+        // nobody listening is fine
+        let _ = self.events.send(event);
+    }
+
+    pub fn snapshot(&self) -> MiningStats {
+        self.stats.lock().clone()
+    }
+
+    pub fn reset(&self) {
+        *self.stats.lock() = MiningStats::default();
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<MiningEvent> {
+        self.events.subscribe()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use azalea::BlockPos;
+
+    use super::{MiningEvent, MiningStats};
+    use crate::mining::MiningError;
+
+    #[test]
+    fn test_since() {
+        let mut stats = MiningStats::default();
+        stats.record(&MiningEvent::BlockBroken {
+            pos: BlockPos::new(0, 0, 0),
+            block: "stone".to_owned(),
+        });
+        stats.record(&MiningEvent::Pathing(Duration::from_secs(3)));
+        let before = stats.clone();
+
+        stats.record(&MiningEvent::BlockBroken {
+            pos: BlockPos::new(1, 0, 0),
+            block: "iron_ore".to_owned(),
+        });
+        stats.record(&MiningEvent::ItemsGained {
+            item: "raw_iron".to_owned(),
+            count: 2,
+        });
+        stats.record(&MiningEvent::Failed {
+            pos: BlockPos::new(2, 0, 0),
+            error: MiningError::PathFailed,
+        });
+
+        let session = stats.since(&before);
+        assert_eq!(session.total_blocks_broken(), 1);
+        assert_eq!(session.blocks_broken.get("iron_ore"), Some(&1));
+        assert_eq!(session.items_gained.get("raw_iron"), Some(&2));
+        assert_eq!(session.pathing, Duration::ZERO);
+        assert_eq!(session.total_failures(), 1);
+        assert!(stats.since(&stats).is_empty());
+    }
+
+    #[test]
+    fn test_since_reset() {
+        let mut before = MiningStats::default();
+        before.record(&MiningEvent::DurabilityUsed(20));
+        before.record(&MiningEvent::ItemsGained {
+            item: "cobblestone".to_owned(),
+            count: 5,
+        });
+
+        // reset in between
+        let mut stats = MiningStats::default();
+        stats.record(&MiningEvent::DurabilityUsed(3));
+        stats.record(&MiningEvent::ItemsGained {
+            item: "cobblestone".to_owned(),
+            count: 2,
+        });
+        assert!(stats.since(&before).is_empty());
+    }
+}
@@ -0,0 +1,160 @@
+//! Picking which blocks to mine by how hard they are to get to, rather than
+//! only by how close they are.
+
+use azalea::world::ChunkStorage;
+use azalea::{BlockPos, Vec3};
+use serde::{Deserialize, Serialize};
+
+use crate::mining::{can_mine_block, is_fluid};
+use crate::vein::neighbor_offsets;
+
+/// Added to blocks with no air next to them, since they have to be dug to.
+const BURIED_COST: f32 = 12.;
+/// Added to blocks with nowhere to stand near them without digging.
+const NO_STANDING_SPOT_COST: f32 = 6.;
+
+/// Which blocks are looked at when picking targets.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TargetSearch {
+    /// How many of the closest blocks are ranked.
+    pub candidates: usize,
+    /// How far away blocks can be, in blocks.
+    pub radius: f64,
+}
+
+impl Default for TargetSearch {
+    fn default() -> Self {
+        Self {
+            candidates: 48,
+            radius: 48.,
+        }
+    }
+}
+
+/// Sorts blocks by [`target_cost`], cheapest first.
+pub fn rank_targets(
+    chunks: &ChunkStorage,
+    feet: BlockPos,
+    eye_position: Vec3,
+    candidates: &[BlockPos],
+) -> Vec<BlockPos> {
+    let mut ranked: Vec<(BlockPos, f32)> = candidates
+        .iter()
+        .map(|pos| (*pos, target_cost(chunks, feet, eye_position, *pos)))
+        .collect();
+    ranked.sort_by(|a, b| a.1.total_cmp(&b.1));
+    ranked.into_iter().map(|(pos, _)| pos).collect()
+}
+
+/// A rough guess of how many blocks of walking it takes to mine a block.
+/// Blocks that can be mined from where the bot is cost nothing, and going up
+/// or down costs more than going sideways.
+pub fn target_cost(
+    chunks: &ChunkStorage,
+    feet: BlockPos,
+    eye_position: Vec3,
+    pos: BlockPos,
+) -> f32 {
+    if can_mine_block(&pos, eye_position, chunks).is_ok() {
+        return 0.;
+    }
+
+    let dx = (pos.x - feet.x) as f32;
+    let dz = (pos.z - feet.z) as f32;
+    let dy = pos.y - feet.y;
+    // climbing needs pillaring or stairs, and dropping down has to be done
+    // carefully
+    let vertical = if dy > 0 {
+        dy as f32 * 2.
+    } else {
+        -dy as f32 * 1.5
+    };
+    let mut cost = (dx * dx + dz * dz).sqrt() + vertical;
+
+    let is_exposed = neighbor_offsets(false)
+        .into_iter()
+        .any(|offset| is_passable(chunks, pos + offset));
+    if !is_exposed {
+        cost += BURIED_COST;
+    } else if !has_standing_spot(chunks, pos) {
+        cost += NO_STANDING_SPOT_COST;
+    }
+    cost
+}
+
+/// Whether there's somewhere within reach of the block the bot could stand
+/// without digging.
+fn has_standing_spot(chunks: &ChunkStorage, pos: BlockPos) -> bool {
+    for dx in -2..=2 {
+        for dy in -3..=1 {
+            for dz in -2..=2 {
+                let feet = pos + BlockPos::new(dx, dy, dz);
+                if is_passable(chunks, feet)
+                    && is_passable(chunks, feet.up(1))
+                    && is_solid(chunks, feet.down(1))
+                {
+                    return true;
+                }
+            }
+        }
+    }
+    false
+}
+
+fn is_passable(chunks: &ChunkStorage, pos: BlockPos) -> bool {
+    chunks
+        .get_block_state(pos)
+        .is_some_and(|block_state| block_state.is_air())
+}
+
+fn is_solid(chunks: &ChunkStorage, pos: BlockPos) -> bool {
+    chunks
+        .get_block_state(pos)
+        .is_some_and(|block_state| !block_state.is_air() && !is_fluid(block_state))
+}
+
+#[cfg(test)]
+mod tests {
+    use azalea::blocks::BlockState;
+    use azalea::core::position::ChunkPos;
+    use azalea::registry::Block;
+    use azalea::world::{Chunk, ChunkStorage, PartialChunkStorage};
+    use azalea::{BlockPos, Vec3};
+
+    use super::{rank_targets, target_cost};
+
+    #[test]
+    fn test_rank_targets() {
+        let mut chunks = ChunkStorage::default();
+        let mut partial = PartialChunkStorage::default();
+        partial.set(&ChunkPos::new(0, 0), Some(Chunk::default()), &mut chunks);
+        let stone = BlockState::from(Block::Stone);
+        // solid ground up to y 63, with air above it
+        for x in 0..16 {
+            for y in 55..64 {
+                for z in 0..16 {
+                    chunks.set_block_state(BlockPos::new(x, y, z), stone);
+                }
+            }
+        }
+
+        let feet = BlockPos::new(2, 64, 2);
+        let eye_position = Vec3::new(2.5, 65.62, 2.5);
+        // in reach, on the ground far away, floating up high, and buried
+        let in_reach = BlockPos::new(3, 64, 2);
+        let far = BlockPos::new(12, 64, 2);
+        let high = BlockPos::new(4, 70, 2);
+        let buried = BlockPos::new(8, 58, 8);
+        for pos in [in_reach, far, high] {
+            chunks.set_block_state(pos, stone);
+        }
+
+        assert_eq!(target_cost(&chunks, feet, eye_position, in_reach), 0.);
+        assert_eq!(target_cost(&chunks, feet, eye_position, far), 10.);
+        assert_eq!(
+            rank_targets(&chunks, feet, eye_position, &[buried, high, in_reach, far]),
+            vec![in_reach, far, high, buried]
+        );
+    }
+}
@@ -0,0 +1,302 @@
+//! Commands queued to run one after another on a bot, saved to disk so they
+//! can be resumed after a reconnect or a restart.
+
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::commands::CommandSender;
+use crate::commands::reply::ReplyMode;
+
+pub type QueueId = u64;
+
+/// A command that is queued or running.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QueuedCommand {
+    pub id: QueueId,
+    /// The command without its prefix, e.g. `mineall oak_log`.
+    pub input: String,
+    pub sender: QueuedSender,
+    pub reply_mode: ReplyMode,
+}
+
+/// Who queued a command. Their permission level isn't saved, since it could
+/// change before the command runs, so it has to be looked up again.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QueuedSender {
+    pub name: Option<String>,
+    pub uuid: Option<Uuid>,
+}
+
+impl From<CommandSender> for QueuedSender {
+    fn from(sender: CommandSender) -> Self {
+        Self {
+            name: sender.name,
+            uuid: sender.uuid,
+        }
+    }
+}
+
+/// The queue of a single bot.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct BotQueue {
+    /// The command that is running, or was running when the bot disconnected.
+    pub current: Option<QueuedCommand>,
+    /// Commands waiting for the current one to end, next first.
+    pub queued: VecDeque<QueuedCommand>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct QueueFile {
+    next_id: QueueId,
+    bots: HashMap<String, BotQueue>,
+}
+
+/// The queues of every bot, saved to a json file after every change.
+#[derive(Debug, Clone, Default)]
+pub struct TaskQueues {
+    /// Where the queues are saved, or `None` to keep them in memory.
+    path: Option<PathBuf>,
+    file: Arc<Mutex<QueueFile>>,
+}
+
+impl TaskQueues {
+    /// Loads the queues from a file. A missing file gives empty queues.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, TaskQueueError> {
+        let path = path.as_ref();
+        let file = match fs::read_to_string(path) {
+            Ok(contents) => serde_json::from_str(&contents)?,
+            Err(err) if err.kind() == ErrorKind::NotFound => QueueFile::default(),
+            Err(err) => return Err(err.into()),
+        };
+
+        Ok(Self {
+            path: Some(path.to_owned()),
+            file: Arc::new(Mutex::new(file)),
+        })
+    }
+
+    /// The queue of a bot.
+    pub fn bot(&self, username: &str) -> TaskQueue {
+        TaskQueue {
+            username: username.to_owned(),
+            queues: self.clone(),
+        }
+    }
+
+    fn save(&self, file: &QueueFile) -> Result<(), TaskQueueError> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let contents = serde_json::to_string_pretty(file)?;
+        fs::write(path, contents)?;
+        Ok(())
+    }
+}
+
+/// The queue of a single bot, shared with its other connections.
+#[derive(Debug, Clone, Default)]
+pub struct TaskQueue {
+    username: String,
+    queues: TaskQueues,
+}
+
+impl TaskQueue {
+    /// A snapshot of the queue.
+    pub fn get(&self) -> BotQueue {
+        self.queues
+            .file
+            .lock()
+            .bots
+            .get(&self.username)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Makes a command the current one, replacing whatever was running.
+    pub fn start(&self, input: &str, sender: CommandSender, reply_mode: ReplyMode) -> QueueId {
+        self.update(|queue, id| {
+            queue.current = Some(QueuedCommand {
+                id,
+                input: input.to_owned(),
+                sender: sender.into(),
+                reply_mode,
+            });
+            id
+        })
+    }
+
+    /// Adds a command to run after the others.
+    pub fn push(&self, input: &str, sender: CommandSender, reply_mode: ReplyMode) -> QueueId {
+        self.update(|queue, id| {
+            queue.queued.push_back(QueuedCommand {
+                id,
+                input: input.to_owned(),
+                sender: sender.into(),
+                reply_mode,
+            });
+            id
+        })
+    }
+
+    /// Ends the current command if it has this id. Returns false if it was
+    /// already replaced by another command.
+    pub fn finish(&self, id: QueueId) -> bool {
+        self.update(|queue, _| {
+            if queue
+                .current
+                .as_ref()
+                .is_some_and(|current| current.id == id)
+            {
+                queue.current = None;
+                return true;
+            }
+            false
+        })
+    }
+
+    /// Removes the current command, e.g. when it's cancelled.
+    pub fn take_current(&self) -> Option<QueuedCommand> {
+        self.update(|queue, _| queue.current.take())
+    }
+
+    /// Puts the current command back at the front of the queue, so it runs
+    /// again next.
+    pub fn requeue_current(&self) {
+        self.update(|queue, _| {
+            if let Some(current) = queue.current.take() {
+                queue.queued.push_front(current);
+            }
+        });
+    }
+
+    /// Takes the next command out of the queue.
+    pub fn pop_next(&self) -> Option<QueuedCommand> {
+        self.update(|queue, _| queue.queued.pop_front())
+    }
+
+    /// Removes a command that hasn't started yet.
+    pub fn remove(&self, id: QueueId) -> bool {
+        self.update(|queue, _| {
+            let len = queue.queued.len();
+            queue.queued.retain(|command| command.id != id);
+            queue.queued.len() != len
+        })
+    }
+
+    /// Removes every command, including the current one, and returns how many
+    /// were removed.
+    pub fn clear(&self) -> usize {
+        self.update(|queue, _| {
+            let removed = queue.queued.len() + usize::from(queue.current.is_some());
+            *queue = BotQueue::default();
+            removed
+        })
+    }
+
+    /// Changes the queue of this bot, and saves every queue if it changed.
+    /// `f` gets an id it can use for a new command.
+    fn update<R>(&self, f: impl FnOnce(&mut BotQueue, QueueId) -> R) -> R {
+        let mut file = self.queues.file.lock();
+        let id = file.next_id + 1;
+
+        let queue = file.bots.entry(self.username.clone()).or_default();
+        let before = queue.clone();
+        let result = f(queue, id);
+        let changed = *queue != before;
+        if *queue == BotQueue::default() {
+            file.bots.remove(&self.username);
+        }
+        if !changed {
+            return result;
+        }
+
+        file.next_id = id;
+        if let Err(err) = self.queues.save(&file) {
+            warn!("Could not save the task queue of {}: {err}", self.username);
+        }
+        result
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum TaskQueueError {
+    #[error("Could not read or write the task queue: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Invalid task queue: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::{TaskQueueError, TaskQueues};
+    use crate::commands::CommandSender;
+    use crate::commands::reply::ReplyMode;
+
+    fn sender() -> CommandSender {
+        CommandSender {
+            name: Some("player".to_owned()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_queue() {
+        let queue = TaskQueues::default().bot("lickbot");
+        let first = queue.push("mine stone", sender(), ReplyMode::Chat);
+        let second = queue.push("mineall oak_log", sender(), ReplyMode::Chat);
+        let third = queue.push("come", sender(), ReplyMode::Whisper);
+        assert!(first < second && second < third);
+
+        assert!(queue.remove(second));
+        assert!(!queue.remove(second));
+        let next = queue.pop_next().unwrap();
+        assert_eq!(next.id, first);
+        assert_eq!(next.sender.name.as_deref(), Some("player"));
+
+        let current = queue.start(&next.input, sender(), next.reply_mode);
+        assert!(queue.finish(current));
+        assert!(!queue.finish(current));
+        assert_eq!(queue.pop_next().unwrap().input, "come");
+        assert!(queue.pop_next().is_none());
+
+        // the other bots have their own queues
+        let other = TaskQueues::default().bot("other");
+        assert!(other.pop_next().is_none());
+    }
+
+    #[test]
+    fn test_save_only_changes() -> Result<(), TaskQueueError> {
+        let path =
+            std::env::temp_dir().join(format!("lickbot-test-tasks-{}.json", std::process::id()));
+        let _ = fs::remove_file(&path);
+
+        let queue = TaskQueues::load(&path)?.bot("lickbot");
+        assert!(!queue.remove(1));
+        assert!(queue.pop_next().is_none());
+        // nothing changed, so nothing was written
+        assert!(!path.exists());
+
+        let id = queue.push("mine stone", sender(), ReplyMode::Chat);
+        queue.start("come", sender(), ReplyMode::Chat);
+
+        let loaded = TaskQueues::load(&path)?.bot("lickbot");
+        assert_eq!(loaded.get(), queue.get());
+        assert_eq!(loaded.get().queued[0].id, id);
+        // ids keep going up after loading
+        assert!(loaded.push("collect", sender(), ReplyMode::Chat) > id);
+
+        fs::remove_file(&path)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,355 @@
+use std::fmt::Display;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex;
+use tokio::sync::Notify;
+use tokio::task::AbortHandle;
+use tracing::{debug, info};
+
+pub type TaskId = u32;
+
+/// How many tasks that are no longer running are kept around for listing.
+const MAX_FINISHED_TASKS: usize = 10;
+
+/// What to do with tasks that are already running when a new one is spawned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpawnMode {
+    /// Cancel every running task first.
+    Preempt,
+    /// Run next to the other tasks.
+    Parallel,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TaskStatus {
+    Running,
+    Finished,
+    Failed(String),
+    Cancelled,
+}
+
+impl Display for TaskStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TaskStatus::Running => write!(f, "running"),
+            TaskStatus::Finished => write!(f, "finished"),
+            TaskStatus::Failed(err) => write!(f, "failed ({err})"),
+            TaskStatus::Cancelled => write!(f, "cancelled"),
+        }
+    }
+}
+
+/// A snapshot of a task.
+#[derive(Debug, Clone)]
+pub struct TaskInfo {
+    pub id: TaskId,
+    pub name: String,
+    pub status: TaskStatus,
+    /// Set by the task itself, e.g. `mined 12 blocks`.
+    pub progress: Option<String>,
+    pub started: Instant,
+    pub ended: Option<Instant>,
+}
+
+impl TaskInfo {
+    /// How long the task has been running, or how long it ran for.
+    pub fn elapsed(&self) -> Duration {
+        self.ended.unwrap_or_else(Instant::now) - self.started
+    }
+}
+
+#[derive(Debug)]
+struct TaskEntry {
+    info: TaskInfo,
+    abort_handle: Option<AbortHandle>,
+}
+
+#[derive(Debug, Default)]
+struct TaskManagerInner {
+    next_id: TaskId,
+    tasks: Vec<TaskEntry>,
+}
+
+/// Keeps track of the background tasks of a bot, so they can be listed and
+/// cancelled.
+///
+/// Cancelling a task drops its future at the next `.await`.
+#[derive(Debug, Clone, Default)]
+pub struct TaskManager {
+    inner: Arc<Mutex<TaskManagerInner>>,
+    /// Woken up whenever a task ends.
+    ended: Arc<Notify>,
+}
+
+impl TaskManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Spawns a task on the tokio runtime and returns its id.
+    pub fn spawn<F, Fut>(&self, name: impl Into<String>, mode: SpawnMode, task: F) -> TaskId
+    where
+        F: FnOnce(TaskHandle) -> Fut,
+        Fut: Future<Output = anyhow::Result<()>> + Send + 'static,
+    {
+        if mode == SpawnMode::Preempt {
+            self.cancel_all();
+        }
+
+        let name = name.into();
+        let id = {
+            let mut inner = self.inner.lock();
+            inner.next_id += 1;
+            let id = inner.next_id;
+            inner.tasks.push(TaskEntry {
+                info: TaskInfo {
+                    id,
+                    name: name.clone(),
+                    status: TaskStatus::Running,
+                    progress: None,
+                    started: Instant::now(),
+                    ended: None,
+                },
+                abort_handle: None,
+            });
+            id
+        };
+        info!("starting task #{id}: {name}");
+
+        let task = task(TaskHandle {
+            id,
+            manager: self.clone(),
+        });
+        let manager = self.clone();
+        let join_handle = tokio::spawn(async move {
+            let status = match task.await {
+                Ok(()) => TaskStatus::Finished,
+                Err(err) => TaskStatus::Failed(err.to_string()),
+            };
+            manager.end(id, status);
+        });
+
+        if let Some(entry) = self
+            .inner
+            .lock()
+            .tasks
+            .iter_mut()
+            .find(|entry| entry.info.id == id)
+        {
+            entry.abort_handle = Some(join_handle.abort_handle());
+        }
+
+        id
+    }
+
+    /// Cancels a running task. Returns false if there is no running task with
+    /// that id.
+    pub fn cancel(&self, id: TaskId) -> bool {
+        let mut inner = self.inner.lock();
+        let Some(entry) = inner
+            .tasks
+            .iter_mut()
+            .find(|entry| entry.info.id == id && entry.info.status == TaskStatus::Running)
+        else {
+            return false;
+        };
+
+        if let Some(abort_handle) = &entry.abort_handle {
+            abort_handle.abort();
+        }
+        entry.info.status = TaskStatus::Cancelled;
+        entry.info.ended = Some(Instant::now());
+        info!("cancelled task #{id}: {}", entry.info.name);
+        prune_ended_tasks(&mut inner);
+        self.ended.notify_waiters();
+
+        true
+    }
+
+    /// Cancels every running task and returns how many were cancelled.
+    pub fn cancel_all(&self) -> usize {
+        self.running()
+            .iter()
+            .filter(|task| self.cancel(task.id))
+            .count()
+    }
+
+    pub fn get(&self, id: TaskId) -> Option<TaskInfo> {
+        self.inner
+            .lock()
+            .tasks
+            .iter()
+            .find(|entry| entry.info.id == id)
+            .map(|entry| entry.info.clone())
+    }
+
+    /// Every task that is running or ended recently, oldest first.
+    pub fn tasks(&self) -> Vec<TaskInfo> {
+        self.inner
+            .lock()
+            .tasks
+            .iter()
+            .map(|entry| entry.info.clone())
+            .collect()
+    }
+
+    pub fn running(&self) -> Vec<TaskInfo> {
+        self.tasks()
+            .into_iter()
+            .filter(|task| task.status == TaskStatus::Running)
+            .collect()
+    }
+
+    /// Waits until a task isn't running anymore.
+    pub async fn wait(&self, id: TaskId) {
+        loop {
+            // registered before checking so an end in between isn't missed
+            let ended = self.ended.notified();
+            if self
+                .get(id)
+                .is_none_or(|task| task.status != TaskStatus::Running)
+            {
+                return;
+            }
+            ended.await;
+        }
+    }
+
+    fn end(&self, id: TaskId, status: TaskStatus) {
+        let mut inner = self.inner.lock();
+        if let Some(entry) = inner
+            .tasks
+            .iter_mut()
+            .find(|entry| entry.info.id == id && entry.info.status == TaskStatus::Running)
+        {
+            debug!("task #{id} ended: {status}");
+            entry.info.status = status;
+            entry.info.ended = Some(Instant::now());
+            entry.abort_handle = None;
+        }
+        prune_ended_tasks(&mut inner);
+        self.ended.notify_waiters();
+    }
+}
+
+/// Forgets the oldest tasks that have ended.
+fn prune_ended_tasks(inner: &mut TaskManagerInner) {
+    let ended = inner
+        .tasks
+        .iter()
+        .filter(|entry| entry.info.status != TaskStatus::Running)
+        .count();
+    let mut to_remove = ended.saturating_sub(MAX_FINISHED_TASKS);
+    inner.tasks.retain(|entry| {
+        if to_remove > 0 && entry.info.status != TaskStatus::Running {
+            to_remove -= 1;
+            return false;
+        }
+        true
+    });
+}
+
+/// Given to a task so it can report its progress.
+#[derive(Debug, Clone)]
+pub struct TaskHandle {
+    pub id: TaskId,
+    manager: TaskManager,
+}
+
+impl TaskHandle {
+    pub fn set_progress(&self, progress: impl Into<String>) {
+        if let Some(entry) = self
+            .manager
+            .inner
+            .lock()
+            .tasks
+            .iter_mut()
+            .find(|entry| entry.info.id == self.id)
+        {
+            entry.info.progress = Some(progress.into());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::bail;
+    use tokio::sync::oneshot;
+
+    use super::{MAX_FINISHED_TASKS, SpawnMode, TaskId, TaskManager, TaskStatus};
+
+    async fn wait_for_end(tasks: &TaskManager, id: TaskId) -> TaskStatus {
+        tasks.wait(id).await;
+        tasks.get(id).expect("task should exist").status
+    }
+
+    #[tokio::test]
+    async fn test_spawn() {
+        let tasks = TaskManager::new();
+        let (tx, rx) = oneshot::channel::<()>();
+        let id = tasks.spawn("wait", SpawnMode::Parallel, |handle| async move {
+            handle.set_progress("waiting");
+            rx.await?;
+            Ok(())
+        });
+        tokio::task::yield_now().await;
+        let task = tasks.get(id).unwrap();
+        assert_eq!(task.status, TaskStatus::Running);
+        assert_eq!(task.progress.as_deref(), Some("waiting"));
+
+        tx.send(()).unwrap();
+        assert_eq!(wait_for_end(&tasks, id).await, TaskStatus::Finished);
+        assert!(tasks.get(id).unwrap().ended.is_some());
+
+        let id = tasks.spawn("fail", SpawnMode::Parallel, |_| async { bail!("oops") });
+        assert_eq!(
+            wait_for_end(&tasks, id).await,
+            TaskStatus::Failed("oops".to_owned())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_cancel() {
+        let tasks = TaskManager::new();
+        let forever = |_| std::future::pending::<anyhow::Result<()>>();
+        let first = tasks.spawn("first", SpawnMode::Parallel, forever);
+        let second = tasks.spawn("second", SpawnMode::Parallel, forever);
+        assert_eq!(tasks.running().len(), 2);
+
+        assert!(tasks.cancel(first));
+        assert!(!tasks.cancel(first));
+        assert_eq!(tasks.get(first).unwrap().status, TaskStatus::Cancelled);
+        assert_eq!(tasks.get(second).unwrap().status, TaskStatus::Running);
+
+        // a preempting task cancels the others but not itself
+        let third = tasks.spawn("third", SpawnMode::Preempt, forever);
+        assert_eq!(tasks.get(second).unwrap().status, TaskStatus::Cancelled);
+        assert_eq!(tasks.get(third).unwrap().status, TaskStatus::Running);
+
+        assert_eq!(tasks.cancel_all(), 1);
+        assert!(tasks.running().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_prune() {
+        let tasks = TaskManager::new();
+        let running = tasks.spawn("running", SpawnMode::Parallel, |_| {
+            std::future::pending::<anyhow::Result<()>>()
+        });
+        let mut ended = Vec::new();
+        for i in 0..MAX_FINISHED_TASKS + 3 {
+            let id = tasks.spawn(format!("task {i}"), SpawnMode::Parallel, |_| async {
+                Ok(())
+            });
+            wait_for_end(&tasks, id).await;
+            ended.push(id);
+        }
+
+        let kept: Vec<TaskId> = tasks.tasks().iter().map(|task| task.id).collect();
+        assert_eq!(kept.len(), MAX_FINISHED_TASKS + 1);
+        // the oldest ended tasks are forgotten first
+        assert_eq!(kept[0], running);
+        assert_eq!(kept[1..], ended[3..]);
+    }
+}
@@ -0,0 +1,272 @@
+//! Turning plugins on and off on a bot at runtime.
+//!
+//! Plugins are enabled by inserting their component on the bot, so each one
+//! is registered with a name and its component type. The options of a plugin
+//! are the serialized fields of its component.
+
+use std::collections::HashMap;
+
+use azalea::Client;
+use azalea::ecs::prelude::*;
+use azalea::ecs::world::EntityWorldMut;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use thiserror::Error;
+
+/// A plugin that can be toggled by inserting or removing its component.
+pub struct TogglePlugin {
+    pub name: &'static str,
+    pub description: &'static str,
+    default_options: Value,
+    check: fn(Value) -> Result<(), serde_json::Error>,
+    insert: fn(&mut EntityWorldMut, Value) -> Result<(), serde_json::Error>,
+    remove: fn(&mut EntityWorldMut),
+}
+
+impl TogglePlugin {
+    /// Registers the component `T`. Its options are the fields of
+    /// `T::default()`.
+    ///
+    /// Names should be lowercase without underscores, like `autolook`.
+    pub fn new<T>(name: &'static str, description: &'static str) -> Self
+    where
+        T: Component + Default + Serialize + DeserializeOwned,
+    {
+        Self {
+            name,
+            description,
+            default_options: serde_json::to_value(T::default())
+                .expect("plugin components should serialize to json"),
+            check: check_options::<T>,
+            insert: insert_component::<T>,
+            remove: remove_component::<T>,
+        }
+    }
+
+    pub fn default_options(&self) -> &Value {
+        &self.default_options
+    }
+
+    /// The names of the options, empty for plugins without options.
+    pub fn option_names(&self) -> Vec<&str> {
+        match &self.default_options {
+            Value::Object(options) => options.keys().map(String::as_str).collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Sets an option in `state`, checking the value works for the component.
+    /// Values are parsed as json, falling back to a string.
+    pub fn set_option(
+        &self,
+        state: &mut PluginState,
+        option: &str,
+        value: &str,
+    ) -> Result<(), ToggleError> {
+        if !self.option_names().contains(&option) {
+            return Err(ToggleError::UnknownOption {
+                plugin: self.name,
+                option: option.to_owned(),
+            });
+        }
+
+        let value = serde_json::from_str(value).unwrap_or_else(|_| Value::String(value.to_owned()));
+        let mut options = self.options(state);
+        options[option] = value;
+
+        (self.check)(options.clone()).map_err(|err| ToggleError::InvalidOption {
+            plugin: self.name,
+            option: option.to_owned(),
+            reason: err.to_string(),
+        })?;
+
+        state.options = Some(options);
+        Ok(())
+    }
+
+    /// The options in `state`, or the defaults if none were set.
+    pub fn options(&self, state: &PluginState) -> Value {
+        state
+            .options
+            .clone()
+            .unwrap_or_else(|| self.default_options.clone())
+    }
+
+    /// Inserts or removes the component to match `state`.
+    fn apply(&self, entity: &mut EntityWorldMut, state: &PluginState) -> Result<(), ToggleError> {
+        if !state.enabled {
+            (self.remove)(entity);
+            return Ok(());
+        }
+
+        (self.insert)(entity, self.options(state)).map_err(|err| ToggleError::InvalidOptions {
+            plugin: self.name,
+            reason: err.to_string(),
+        })
+    }
+}
+
+fn check_options<T: DeserializeOwned>(options: Value) -> Result<(), serde_json::Error> {
+    serde_json::from_value::<T>(options)?;
+    Ok(())
+}
+
+fn insert_component<T: Component + DeserializeOwned>(
+    entity: &mut EntityWorldMut,
+    options: Value,
+) -> Result<(), serde_json::Error> {
+    entity.insert(serde_json::from_value::<T>(options)?);
+    Ok(())
+}
+
+fn remove_component<T: Component>(entity: &mut EntityWorldMut) {
+    entity.remove::<T>();
+}
+
+/// Whether a plugin is enabled on a bot, and its options.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PluginState {
+    pub enabled: bool,
+    /// `None` uses the defaults of the component.
+    pub options: Option<Value>,
+}
+
+/// The state of every plugin on a bot, by plugin name. Plugins that aren't in
+/// here are disabled.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PluginStates(HashMap<String, PluginState>);
+
+impl PluginStates {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, name: &str) -> PluginState {
+        self.0.get(name).cloned().unwrap_or_default()
+    }
+
+    pub fn get_mut(&mut self, name: &str) -> &mut PluginState {
+        self.0.entry(name.to_owned()).or_default()
+    }
+
+    pub fn set(&mut self, name: &str, state: PluginState) {
+        self.0.insert(name.to_owned(), state);
+    }
+
+    /// Takes the states from `new` that are different from `old`, keeping any
+    /// changes made since `old` to the other plugins.
+    pub fn update(&mut self, old: &PluginStates, new: &PluginStates) {
+        for (name, state) in &new.0 {
+            if old.0.get(name) != Some(state) {
+                self.0.insert(name.clone(), state.clone());
+            }
+        }
+    }
+}
+
+/// Every plugin that can be toggled, by name.
+#[derive(Default)]
+pub struct ToggleRegistry {
+    plugins: Vec<TogglePlugin>,
+}
+
+impl ToggleRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register(&mut self, plugin: TogglePlugin) -> &mut Self {
+        self.plugins.push(plugin);
+        self
+    }
+
+    /// Finds a plugin by name, ignoring case and underscores, so `AutoLook`
+    /// and `auto_look` both find `autolook`.
+    pub fn get(&self, name: &str) -> Option<&TogglePlugin> {
+        let name = name.replace(['_', '-'], "").to_lowercase();
+        self.plugins.iter().find(|plugin| plugin.name == name)
+    }
+
+    pub fn plugins(&self) -> impl Iterator<Item = &TogglePlugin> {
+        self.plugins.iter()
+    }
+
+    /// Inserts or removes the component of every plugin on the bot to match
+    /// `states`. Plugins with invalid options are skipped.
+    pub fn apply(&self, bot: &Client, states: &PluginStates) -> Result<(), ToggleError> {
+        let mut ecs = bot.ecs.lock();
+        let mut entity = ecs.entity_mut(bot.entity);
+
+        let mut result = Ok(());
+        for plugin in &self.plugins {
+            if let Err(err) = plugin.apply(&mut entity, &states.get(plugin.name)) {
+                result = Err(err);
+            }
+        }
+        result
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ToggleError {
+    #[error("Unknown plugin: {0}")]
+    UnknownPlugin(String),
+    #[error("{plugin} has no option {option}")]
+    UnknownOption {
+        plugin: &'static str,
+        option: String,
+    },
+    #[error("Invalid value for {plugin}.{option}: {reason}")]
+    InvalidOption {
+        plugin: &'static str,
+        option: String,
+        reason: String,
+    },
+    #[error("Invalid options for {plugin}: {reason}")]
+    InvalidOptions {
+        plugin: &'static str,
+        reason: String,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use azalea::ecs::prelude::*;
+    use serde::{Deserialize, Serialize};
+
+    use super::{PluginState, ToggleError, TogglePlugin};
+
+    #[derive(Component, Default, Serialize, Deserialize)]
+    #[serde(default)]
+    struct TestPlugin {
+        enabled_thing: bool,
+        count: u32,
+    }
+
+    #[test]
+    fn test_set_option() {
+        let plugin = TogglePlugin::new::<TestPlugin>("test", "");
+        let mut state = PluginState::default();
+
+        plugin.set_option(&mut state, "count", "5").unwrap();
+        plugin
+            .set_option(&mut state, "enabled_thing", "true")
+            .unwrap();
+        assert_eq!(
+            plugin.options(&state),
+            serde_json::json!({ "enabled_thing": true, "count": 5 })
+        );
+
+        assert!(matches!(
+            plugin.set_option(&mut state, "count", "lots"),
+            Err(ToggleError::InvalidOption { .. })
+        ));
+        assert!(matches!(
+            plugin.set_option(&mut state, "missing", "1"),
+            Err(ToggleError::UnknownOption { .. })
+        ));
+        // failed changes are not kept
+        assert_eq!(plugin.options(&state)["count"], 5);
+    }
+}
@@ -0,0 +1,461 @@
+//! Choosing tools for mining without wearing them down to nothing, and with
+//! the right enchantments for the block.
+
+use std::collections::BTreeMap;
+
+use azalea::auto_tool::accurate_best_tool_in_hotbar_for_block;
+use azalea::blocks::{BlockState, BlockTrait};
+use azalea::ecs::prelude::*;
+use azalea::inventory::{ItemStack, Menu, components};
+use azalea::registry::Enchantment;
+use serde::{Deserialize, Serialize};
+
+use crate::commands::args::registry_name;
+use crate::vein::vein_kind;
+
+/// Keeps tools from breaking while mining. While this is on the bot, tools
+/// with less durability left than `min_remaining` aren't used, and a backup
+/// from the inventory is used instead.
+#[derive(Component, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ToolProtection {
+    /// Uses a tool needs to have left to be used.
+    pub min_remaining: u32,
+}
+
+impl Default for ToolProtection {
+    fn default() -> Self {
+        Self { min_remaining: 10 }
+    }
+}
+
+/// Which enchantment a tool should have for a block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ToolPreference {
+    /// The most Fortune, for ores that drop more with it.
+    Fortune,
+    /// Silk Touch, for blocks that would drop nothing or something else
+    /// without it.
+    SilkTouch,
+    /// A tool without Fortune or Silk Touch, to save the enchanted ones.
+    Plain,
+    /// Whatever is fastest.
+    Any,
+}
+
+/// Picks tools with the enchantments that are worth it for each block. While
+/// this is on the bot, `preferences` decides for the blocks in it, and the
+/// built in choices decide for the rest, see [`default_preference`].
+#[derive(Component, Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ToolPolicy {
+    /// Preferences by block name, like `diamond_ore`. Deepslate ores use the
+    /// preference of their stone variant unless they have their own.
+    pub preferences: BTreeMap<String, ToolPreference>,
+}
+
+impl ToolPolicy {
+    pub fn preference_for(&self, block_id: &str) -> ToolPreference {
+        self.preferences
+            .get(block_id)
+            .or_else(|| self.preferences.get(vein_kind(block_id)))
+            .copied()
+            .unwrap_or_else(|| default_preference(block_id))
+    }
+}
+
+/// Fortune for ores that drop more with it, Silk Touch for glass, ice and
+/// ender chests, and plain tools for everything else.
+pub fn default_preference(block_id: &str) -> ToolPreference {
+    let id = vein_kind(block_id);
+    match id {
+        "coal_ore" | "diamond_ore" | "emerald_ore" | "lapis_ore" | "redstone_ore" => {
+            ToolPreference::Fortune
+        }
+        "glass" | "glass_pane" | "ice" | "packed_ice" | "blue_ice" | "ender_chest" => {
+            ToolPreference::SilkTouch
+        }
+        _ if id.ends_with("_glass") || id.ends_with("_glass_pane") => ToolPreference::SilkTouch,
+        _ => ToolPreference::Plain,
+    }
+}
+
+/// The level of an enchantment on an item, or 0 if it doesn't have it.
+pub fn enchantment_level(item: &ItemStack, enchantment: Enchantment) -> u32 {
+    let ItemStack::Present(item_data) = item else {
+        return 0;
+    };
+    item_data
+        .get_component::<components::Enchantments>()
+        .and_then(|enchantments| {
+            enchantments
+                .levels
+                .get(&enchantment)
+                .map(|level| *level as u32)
+        })
+        .unwrap_or_default()
+}
+
+/// How well an item matches a preference, higher is better.
+fn preference_score(item: &ItemStack, preference: ToolPreference) -> u32 {
+    let fortune = enchantment_level(item, Enchantment::Fortune);
+    let silk_touch = enchantment_level(item, Enchantment::SilkTouch);
+    match preference {
+        ToolPreference::Fortune => fortune,
+        ToolPreference::SilkTouch => silk_touch.min(1),
+        ToolPreference::Plain => u32::from(fortune == 0 && silk_touch == 0),
+        ToolPreference::Any => 0,
+    }
+}
+
+/// The tier of a tool, from wood and gold at 0 to netherite at 4.
+fn tool_tier(item: &ItemStack) -> Option<u8> {
+    let ItemStack::Present(item_data) = item else {
+        return None;
+    };
+    let name = registry_name(&item_data.kind);
+    let (material, tool) = name.rsplit_once('_')?;
+    if !matches!(tool, "pickaxe" | "axe" | "shovel" | "hoe" | "sword") {
+        return None;
+    }
+    let tier = match material {
+        "wooden" | "golden" => 0,
+        "stone" | "copper" => 1,
+        "iron" => 2,
+        "diamond" => 3,
+        "netherite" => 4,
+        _ => return None,
+    };
+    Some(tier)
+}
+
+/// The tier of a pickaxe, or `None` for anything else.
+fn pickaxe_tier(item: &ItemStack) -> Option<u8> {
+    let ItemStack::Present(item_data) = item else {
+        return None;
+    };
+    if !registry_name(&item_data.kind).ends_with("_pickaxe") {
+        return None;
+    }
+    tool_tier(item)
+}
+
+/// The lowest pickaxe tier a block drops anything with, for the blocks where
+/// a lower one would still mine it, just with nothing dropped.
+fn min_pickaxe_tier(block_id: &str) -> Option<u8> {
+    let tier = match vein_kind(block_id) {
+        "coal_ore" | "nether_gold_ore" | "nether_quartz_ore" => 0,
+        "copper_ore" | "iron_ore" | "lapis_ore" => 1,
+        "gold_ore" | "diamond_ore" | "emerald_ore" | "redstone_ore" => 2,
+        "obsidian" | "crying_obsidian" | "ancient_debris" => 3,
+        _ => return None,
+    };
+    Some(tier)
+}
+
+/// How many more uses an item has before it breaks, or `None` if it doesn't
+/// have durability.
+pub fn remaining_durability(item: &ItemStack) -> Option<i32> {
+    let ItemStack::Present(item_data) = item else {
+        return None;
+    };
+    let max_damage = item_data.get_component::<components::MaxDamage>()?.amount;
+    let damage = item_data
+        .get_component::<components::Damage>()
+        .map(|damage| damage.amount)
+        .unwrap_or_default();
+    Some(max_damage - damage)
+}
+
+/// How much damage the items have taken altogether.
+pub fn total_damage(items: &[ItemStack]) -> i32 {
+    items
+        .iter()
+        .filter_map(|item| match item {
+            ItemStack::Present(item_data) => item_data.get_component::<components::Damage>(),
+            ItemStack::Empty => None,
+        })
+        .map(|damage| damage.amount)
+        .sum()
+}
+
+/// Whether an item is too worn to use with the given protection.
+pub fn is_worn_out(item: &ItemStack, protection: &ToolProtection) -> bool {
+    remaining_durability(item).is_some_and(|remaining| remaining < protection.min_remaining as i32)
+}
+
+/// The tool picked for a block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToolChoice {
+    /// The tool in this hotbar slot, 0 to 8.
+    Hotbar(usize),
+    /// The tool in this slot of the player's inventory menu, which has to be
+    /// moved to the hotbar first.
+    Inventory(usize),
+}
+
+/// A tool that could be used for a block.
+struct Candidate {
+    slot: usize,
+    tier: Option<u8>,
+    worn_out: bool,
+    in_hotbar: bool,
+    speed: f32,
+    /// How well it matches the policy's preference for the block.
+    score: u32,
+    /// Whether it gets drops from the block and is worth using on it.
+    useful: bool,
+}
+
+/// Picks a tool for a block from the whole inventory, skipping tools that
+/// are worn out. Tools that can get drops from the block are ranked by how
+/// well they match the policy's preference for it, then by speed. When the
+/// tool that would be picked is worn out, only backups of the same or a
+/// lower tier are used. Returns `None` when there is no such backup, since
+/// mining without one could lose the drops or wear down a better tool.
+pub fn best_tool_for_block(
+    block_state: BlockState,
+    menu: &Menu,
+    protection: &ToolProtection,
+    policy: &ToolPolicy,
+) -> Option<ToolChoice> {
+    let block: Box<dyn BlockTrait> = block_state.into();
+    let preference = policy.preference_for(block.id());
+    let min_tier = min_pickaxe_tier(block.id());
+    let hotbar = menu.hotbar_slots_range();
+    let hand_speed = mining_speed(block_state, menu, &ItemStack::Empty);
+
+    let mut candidates = Vec::new();
+    for slot in menu.player_slots_range() {
+        let Some(item) = menu.slot(slot) else {
+            continue;
+        };
+        if item.is_empty() {
+            continue;
+        }
+        let speed = mining_speed(block_state, menu, item);
+        let score = preference_score(item, preference);
+        let gets_drops =
+            min_tier.is_none_or(|min| pickaxe_tier(item).is_some_and(|tier| tier >= min));
+        // silk touch is worth it even on blocks no tool is faster for
+        let useful = gets_drops
+            && (speed > hand_speed || (preference == ToolPreference::SilkTouch && score > 0));
+        candidates.push(Candidate {
+            slot,
+            tier: tool_tier(item),
+            worn_out: is_worn_out(item, protection),
+            in_hotbar: hotbar.contains(&slot),
+            speed,
+            score,
+            useful,
+        });
+    }
+
+    // the best useful tool ranked by preference, speed, then being in the
+    // hotbar already, otherwise the fastest one still beats a hand
+    let pick = |allowed: &dyn Fn(&Candidate) -> bool| {
+        let mut best: Option<&Candidate> = None;
+        let mut fastest: Option<&Candidate> = None;
+        for candidate in candidates.iter().filter(|candidate| allowed(candidate)) {
+            let speed_rank = (candidate.speed, candidate.in_hotbar);
+            if candidate.speed > hand_speed
+                && fastest.is_none_or(|fastest| speed_rank > (fastest.speed, fastest.in_hotbar))
+            {
+                fastest = Some(candidate);
+            }
+            let rank = (candidate.score, candidate.speed, candidate.in_hotbar);
+            if candidate.useful
+                && best.is_none_or(|best| rank > (best.score, best.speed, best.in_hotbar))
+            {
+                best = Some(candidate);
+            }
+        }
+        best.or(fastest)
+    };
+    let preferred = pick(&|_| true);
+    // a worn out tool is replaced with one of the same or a lower tier
+    let max_tier = preferred
+        .filter(|preferred| preferred.worn_out)
+        .and_then(|preferred| preferred.tier);
+    let backup = pick(&|candidate| {
+        !candidate.worn_out
+            && max_tier.is_none_or(|max| candidate.tier.is_none_or(|tier| tier <= max))
+    });
+
+    let slot = match backup {
+        Some(candidate) => candidate.slot,
+        None if preferred.is_some() => return None,
+        None => {
+            // nothing beats a hand, so use an empty slot or anything that
+            // isn't worn out
+            return first_safe_hotbar_slot(menu, protection).map(ToolChoice::Hotbar);
+        }
+    };
+    if hotbar.contains(&slot) {
+        Some(ToolChoice::Hotbar(slot - hotbar.start))
+    } else {
+        Some(ToolChoice::Inventory(slot))
+    }
+}
+
+/// A hotbar slot holding nothing that would be worn down, preferring empty
+/// slots.
+fn first_safe_hotbar_slot(menu: &Menu, protection: &ToolProtection) -> Option<usize> {
+    let hotbar = &menu.slots()[menu.hotbar_slots_range()];
+    hotbar
+        .iter()
+        .position(ItemStack::is_empty)
+        .or_else(|| {
+            hotbar
+                .iter()
+                .position(|item| remaining_durability(item).is_none())
+        })
+        .or_else(|| {
+            hotbar
+                .iter()
+                .position(|item| !is_worn_out(item, protection))
+        })
+}
+
+/// How much of the block is mined per tick while holding `item`.
+fn mining_speed(block_state: BlockState, menu: &Menu, item: &ItemStack) -> f32 {
+    // put the item alone in the hotbar so azalea works it out for just it
+    let mut menu = menu.clone();
+    for slot in menu.hotbar_slots_range() {
+        if let Some(hotbar_item) = menu.slot_mut(slot) {
+            *hotbar_item = ItemStack::Empty;
+        }
+    }
+    if let Some(first) = menu.slot_mut(menu.hotbar_slots_range().start) {
+        *first = item.clone();
+    }
+    accurate_best_tool_in_hotbar_for_block(block_state, &menu).percentage_per_tick
+}
+
+#[cfg(test)]
+mod tests {
+    use azalea::blocks::BlockState;
+    use azalea::inventory::{DataComponentPatch, ItemStack, ItemStackData, Menu, Player};
+    use azalea::registry::{Block, Item};
+
+    use super::{
+        ToolChoice, ToolPolicy, ToolPreference, ToolProtection, best_tool_for_block,
+        default_preference, is_worn_out, min_pickaxe_tier, remaining_durability,
+    };
+
+    fn item(kind: Item) -> ItemStack {
+        ItemStack::Present(ItemStackData {
+            kind,
+            count: 1,
+            component_patch: DataComponentPatch::default(),
+        })
+    }
+
+    #[test]
+    fn test_preference_for() {
+        assert_eq!(
+            default_preference("deepslate_diamond_ore"),
+            ToolPreference::Fortune
+        );
+        assert_eq!(
+            default_preference("red_stained_glass"),
+            ToolPreference::SilkTouch
+        );
+        assert_eq!(default_preference("stone"), ToolPreference::Plain);
+
+        let mut policy = ToolPolicy::default();
+        policy
+            .preferences
+            .insert("diamond_ore".to_owned(), ToolPreference::SilkTouch);
+        assert_eq!(
+            policy.preference_for("deepslate_diamond_ore"),
+            ToolPreference::SilkTouch
+        );
+        assert_eq!(policy.preference_for("coal_ore"), ToolPreference::Fortune);
+        assert_eq!(min_pickaxe_tier("deepslate_iron_ore"), Some(1));
+        assert_eq!(min_pickaxe_tier("stone"), None);
+    }
+
+    #[test]
+    fn test_remaining_durability() {
+        let pickaxe = item(Item::DiamondPickaxe);
+        assert_eq!(remaining_durability(&pickaxe), Some(1561));
+        assert_eq!(remaining_durability(&item(Item::Dirt)), None);
+        assert_eq!(remaining_durability(&ItemStack::Empty), None);
+
+        assert!(!is_worn_out(&pickaxe, &ToolProtection::default()));
+        assert!(is_worn_out(
+            &pickaxe,
+            &ToolProtection {
+                min_remaining: 2000
+            }
+        ));
+        assert!(!is_worn_out(
+            &item(Item::Dirt),
+            &ToolProtection {
+                min_remaining: 2000
+            }
+        ));
+    }
+
+    #[test]
+    fn test_best_tool_for_block() {
+        let mut menu = Menu::Player(Player::default());
+        // the third hotbar slot, and the second slot of the inventory
+        *menu.slot_mut(38).unwrap() = item(Item::StonePickaxe);
+        *menu.slot_mut(10).unwrap() = item(Item::IronPickaxe);
+        let policy = ToolPolicy::default();
+        let protection = ToolProtection::default();
+        let stone = BlockState::from(Block::Stone);
+        let dirt = BlockState::from(Block::Dirt);
+
+        // the fastest tool, even if it has to be moved to the hotbar
+        assert_eq!(
+            best_tool_for_block(stone, &menu, &protection, &policy),
+            Some(ToolChoice::Inventory(10))
+        );
+        // nothing beats a hand, so an empty hotbar slot
+        assert_eq!(
+            best_tool_for_block(dirt, &menu, &protection, &policy),
+            Some(ToolChoice::Hotbar(0))
+        );
+
+        // only worn out tools are faster than a hand
+        let protection = ToolProtection {
+            min_remaining: 2000,
+        };
+        assert_eq!(
+            best_tool_for_block(stone, &menu, &protection, &policy),
+            None
+        );
+        assert_eq!(
+            best_tool_for_block(dirt, &menu, &protection, &policy),
+            Some(ToolChoice::Hotbar(0))
+        );
+    }
+
+    #[test]
+    fn test_backup_tier() {
+        let mut menu = Menu::Player(Player::default());
+        // golden pickaxes are the fastest but have 32 uses, wooden ones 59
+        *menu.slot_mut(36).unwrap() = item(Item::GoldenPickaxe);
+        *menu.slot_mut(37).unwrap() = item(Item::WoodenPickaxe);
+        *menu.slot_mut(38).unwrap() = item(Item::StonePickaxe);
+        let policy = ToolPolicy::default();
+        let protection = ToolProtection { min_remaining: 40 };
+        let stone = BlockState::from(Block::Stone);
+
+        // the stone pickaxe is faster, but a higher tier than the golden one
+        assert_eq!(
+            best_tool_for_block(stone, &menu, &protection, &policy),
+            Some(ToolChoice::Hotbar(1))
+        );
+
+        *menu.slot_mut(37).unwrap() = ItemStack::Empty;
+        assert_eq!(
+            best_tool_for_block(stone, &menu, &protection, &policy),
+            None
+        );
+    }
+}
@@ -0,0 +1,459 @@
+//! Digging straight tunnels, optionally with side branches for strip mining,
+//! and finding the ores they expose.
+
+use std::collections::HashSet;
+use std::fmt::Display;
+use std::str::FromStr;
+
+use azalea::blocks::BlockTrait;
+use azalea::pathfinder::goals::BlockPosGoal;
+use azalea::{BlockPos, Client};
+use thiserror::Error;
+use tracing::{debug, info};
+
+use crate::hazards::check_hazards;
+use crate::mining::{
+    MiningError, MiningExtrasClientExt, StopPathfindingOnDrop, can_mine_block, goto_blocks,
+    is_breakable, is_fluid,
+};
+use crate::unload::UnloadError;
+use crate::vein::{neighbor_offsets, vein_kind_at};
+
+/// How many times a block is mined before giving up, since gravel and sand
+/// keep falling into the same spot.
+const MAX_DIG_ATTEMPTS: usize = 12;
+
+/// A direction along the x or z axis.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Heading {
+    North,
+    South,
+    East,
+    West,
+}
+
+impl Heading {
+    /// The block one step this way.
+    pub fn offset(self) -> BlockPos {
+        match self {
+            Heading::North => BlockPos::new(0, 0, -1),
+            Heading::South => BlockPos::new(0, 0, 1),
+            Heading::East => BlockPos::new(1, 0, 0),
+            Heading::West => BlockPos::new(-1, 0, 0),
+        }
+    }
+
+    pub fn left(self) -> Self {
+        match self {
+            Heading::North => Heading::West,
+            Heading::West => Heading::South,
+            Heading::South => Heading::East,
+            Heading::East => Heading::North,
+        }
+    }
+
+    pub fn right(self) -> Self {
+        match self {
+            Heading::North => Heading::East,
+            Heading::East => Heading::South,
+            Heading::South => Heading::West,
+            Heading::West => Heading::North,
+        }
+    }
+
+    /// The position `distance` blocks this way from `pos`.
+    pub fn step(self, pos: BlockPos, distance: i32) -> BlockPos {
+        let offset = self.offset();
+        BlockPos::new(
+            pos.x + offset.x * distance,
+            pos.y,
+            pos.z + offset.z * distance,
+        )
+    }
+}
+
+impl FromStr for Heading {
+    type Err = TunnelArgError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "n" | "north" => Ok(Heading::North),
+            "s" | "south" => Ok(Heading::South),
+            "e" | "east" => Ok(Heading::East),
+            "w" | "west" => Ok(Heading::West),
+            _ => Err(TunnelArgError::InvalidHeading(s.to_owned())),
+        }
+    }
+}
+
+impl Display for Heading {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Heading::North => write!(f, "north"),
+            Heading::South => write!(f, "south"),
+            Heading::East => write!(f, "east"),
+            Heading::West => write!(f, "west"),
+        }
+    }
+}
+
+/// How big the tunnel is, width by height.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TunnelSize {
+    /// 1x2, just enough to walk through.
+    #[default]
+    Small,
+    /// 3x3, centered on where the bot walks.
+    Large,
+}
+
+impl TunnelSize {
+    /// The blocks to dig for one step of the tunnel, top first, where `pos`
+    /// is where the bot's feet go.
+    pub fn cross_section(self, pos: BlockPos, heading: Heading) -> Vec<BlockPos> {
+        match self {
+            TunnelSize::Small => vec![pos.up(1), pos],
+            TunnelSize::Large => {
+                let mut blocks = Vec::new();
+                for dy in (0..=2).rev() {
+                    for side in -1..=1 {
+                        blocks.push(heading.right().step(pos, side).up(dy));
+                    }
+                }
+                blocks
+            }
+        }
+    }
+}
+
+impl FromStr for TunnelSize {
+    type Err = TunnelArgError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "1x2" | "small" => Ok(TunnelSize::Small),
+            "3x3" | "large" => Ok(TunnelSize::Large),
+            _ => Err(TunnelArgError::InvalidSize(s.to_owned())),
+        }
+    }
+}
+
+/// What to do with ores in the tunnel walls.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OreMode {
+    Ignore,
+    /// Lists them in the [`TunnelReport`].
+    #[default]
+    Record,
+    /// Lists them and mines the ones that can be reached from the tunnel.
+    Mine,
+}
+
+/// Options for [`MiningExtrasClientExt::dig_tunnel`].
+#[derive(Debug, Clone)]
+pub struct TunnelOpts {
+    pub heading: Heading,
+    /// How many blocks long the main tunnel is.
+    pub length: u32,
+    pub size: TunnelSize,
+    /// Digs a branch to both sides every this many blocks, for strip mining.
+    pub branch_every: Option<u32>,
+    pub branch_length: u32,
+    pub ores: OreMode,
+}
+
+impl TunnelOpts {
+    pub fn new(heading: Heading, length: u32) -> Self {
+        Self {
+            heading,
+            length,
+            size: TunnelSize::default(),
+            branch_every: None,
+            branch_length: 16,
+            ores: OreMode::default(),
+        }
+    }
+}
+
+/// What a tunnel dug and found.
+#[derive(Debug, Clone, Default)]
+pub struct TunnelReport {
+    /// Blocks of tunnel dug, including branches.
+    pub dug: u32,
+    /// Ores seen in the walls, with deepslate ores named like their stone
+    /// variant.
+    pub ores: Vec<(BlockPos, &'static str)>,
+    pub ores_mined: usize,
+    /// Why the main tunnel ended early, if it did.
+    pub stopped: Option<TunnelStop>,
+}
+
+/// Why a tunnel couldn't go any further.
+#[derive(Debug, Clone, Error)]
+pub enum TunnelStop {
+    #[error("{0} can't be broken at {1}")]
+    Unbreakable(&'static str, BlockPos),
+    #[error("hit {0} at {1}")]
+    Fluid(&'static str, BlockPos),
+    #[error("there is no floor at {0}")]
+    NoFloor(BlockPos),
+    #[error("could not dig out {0}")]
+    Stuck(BlockPos),
+    #[error("could not walk to {0}")]
+    CantWalk(BlockPos),
+    #[error("timed out walking to {0}")]
+    WalkTimedOut(BlockPos),
+    #[error("it's not safe to dig {0}: {1}")]
+    Hazard(BlockPos, MiningError),
+    #[error("{0}")]
+    NoTool(MiningError),
+    #[error("{0}")]
+    InventoryFull(UnloadError),
+}
+
+#[derive(Debug, Error)]
+pub enum TunnelArgError {
+    #[error("Unknown direction {0}, use north, south, east or west")]
+    InvalidHeading(String),
+    #[error("Unknown tunnel size {0}, use 1x2 or 3x3")]
+    InvalidSize(String),
+}
+
+/// Whether a block is an ore, by its name.
+pub fn is_ore(id: &str) -> bool {
+    id.ends_with("_ore") || id == "ancient_debris"
+}
+
+/// Digs the tunnel in `opts` starting from where the bot is standing.
+pub(crate) async fn dig_tunnel(
+    bot: &Client,
+    opts: &TunnelOpts,
+    on_progress: impl FnMut(&TunnelReport) + Send,
+) -> TunnelReport {
+    let _guard = StopPathfindingOnDrop(bot);
+    let start = bot.position().to_block_pos_floor();
+    info!(
+        "digging a {} block tunnel {} from {start}",
+        opts.length, opts.heading
+    );
+
+    let mut digger = Digger {
+        bot,
+        opts,
+        report: TunnelReport::default(),
+        seen_ores: HashSet::new(),
+        on_progress,
+    };
+    if let Err(stop) = digger.dig_main(start).await {
+        info!("tunnel stopped: {stop}");
+        digger.report.stopped = Some(stop);
+    }
+    digger.report
+}
+
+struct Digger<'a, F> {
+    bot: &'a Client,
+    opts: &'a TunnelOpts,
+    report: TunnelReport,
+    seen_ores: HashSet<BlockPos>,
+    on_progress: F,
+}
+
+impl<F: FnMut(&TunnelReport) + Send> Digger<'_, F> {
+    async fn dig_main(&mut self, start: BlockPos) -> Result<(), TunnelStop> {
+        let heading = self.opts.heading;
+        for i in 1..=self.opts.length {
+            let pos = heading.step(start, i as i32);
+            self.dig_step(pos, heading).await?;
+
+            let Some(branch_every) = self.opts.branch_every else {
+                continue;
+            };
+            if i % branch_every != 0 {
+                continue;
+            }
+            for side in [heading.left(), heading.right()] {
+                // a branch running into lava shouldn't end the whole thing
+                if let Err(stop) = self.dig_branch(pos, side).await {
+                    debug!("branch {side} from {pos} stopped: {stop}");
+                }
+                self.walk_to(pos).await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn dig_branch(&mut self, start: BlockPos, heading: Heading) -> Result<(), TunnelStop> {
+        for i in 1..=self.opts.branch_length {
+            self.dig_step(heading.step(start, i as i32), heading)
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Digs one step of the tunnel, walks into it and checks the walls.
+    async fn dig_step(&mut self, pos: BlockPos, heading: Heading) -> Result<(), TunnelStop> {
+        self.bot
+            .make_room(None)
+            .await
+            .map_err(TunnelStop::InventoryFull)?;
+
+        let floor = pos.down(1);
+        let floor_state = self
+            .bot
+            .world()
+            .read()
+            .get_block_state(floor)
+            .unwrap_or_default();
+        if floor_state.is_air() || is_fluid(floor_state) {
+            return Err(TunnelStop::NoFloor(floor));
+        }
+
+        let cross_section = self.opts.size.cross_section(pos, heading);
+        for block in &cross_section {
+            self.dig_block(*block).await?;
+        }
+        self.walk_to(pos).await?;
+        self.report.dug += 1;
+
+        if self.opts.ores != OreMode::Ignore {
+            self.check_walls(&cross_section).await;
+        }
+        (self.on_progress)(&self.report);
+        Ok(())
+    }
+
+    async fn dig_block(&self, pos: BlockPos) -> Result<(), TunnelStop> {
+        for _ in 0..MAX_DIG_ATTEMPTS {
+            let block_state = self
+                .bot
+                .world()
+                .read()
+                .get_block_state(pos)
+                .unwrap_or_default();
+            if block_state.is_air() {
+                return Ok(());
+            }
+            let block: Box<dyn BlockTrait> = block_state.into();
+            if is_fluid(block_state) {
+                return Err(TunnelStop::Fluid(block.id(), pos));
+            }
+            if !is_breakable(block_state) {
+                return Err(TunnelStop::Unbreakable(block.id(), pos));
+            }
+            check_hazards(self.bot, &pos).map_err(|err| TunnelStop::Hazard(pos, err))?;
+
+            let reachable = can_mine_block(
+                &pos,
+                self.bot.eye_position(),
+                &self.bot.world().read().chunks,
+            )
+            .is_ok();
+            if reachable {
+                if let Err(err @ MiningError::ToolsWornOut(_)) =
+                    self.bot.mine_block_with_best_tool(&pos).await
+                {
+                    return Err(TunnelStop::NoTool(err));
+                }
+            } else {
+                let result = self.bot.goto_and_try_mine_block(&pos).await;
+                if let Some(err) = result.err().and_then(|err| err.tools_worn_out()) {
+                    return Err(TunnelStop::NoTool(err));
+                }
+            }
+            // let anything above fall in before checking again
+            self.bot.wait_ticks(2).await;
+        }
+        Err(TunnelStop::Stuck(pos))
+    }
+
+    async fn walk_to(&self, pos: BlockPos) -> Result<(), TunnelStop> {
+        goto_blocks(self.bot, &[pos], BlockPosGoal)
+            .await
+            .map_err(|err| match err {
+                MiningError::TimedOut => TunnelStop::WalkTimedOut(pos),
+                _ => TunnelStop::CantWalk(pos),
+            })
+    }
+
+    /// Records the ores next to the cross section, and mines them if the
+    /// options say so.
+    async fn check_walls(&mut self, cross_section: &[BlockPos]) {
+        let mut to_mine = Vec::new();
+        for block in cross_section {
+            to_mine.extend(self.find_ores_around(*block, cross_section));
+        }
+        if self.opts.ores != OreMode::Mine {
+            return;
+        }
+
+        while let Some(ore) = to_mine.pop() {
+            let chunks = self.bot.world().read().chunks.clone();
+            if vein_kind_at(&chunks, ore).is_none_or(|kind| !is_ore(kind)) {
+                continue;
+            }
+            if can_mine_block(&ore, self.bot.eye_position(), &chunks).is_err() {
+                debug!("ore at {ore} can't be reached from the tunnel");
+                continue;
+            }
+
+            let _ = self.bot.mine_block_with_best_tool(&ore).await;
+            if vein_kind_at(&self.bot.world().read().chunks, ore).is_none() {
+                self.report.ores_mined += 1;
+                // mining an ore can expose more of the vein
+                to_mine.extend(self.find_ores_around(ore, cross_section));
+            }
+        }
+    }
+
+    /// Records the ores touching `pos` that haven't been seen yet and returns
+    /// their positions.
+    fn find_ores_around(&mut self, pos: BlockPos, cross_section: &[BlockPos]) -> Vec<BlockPos> {
+        let world = self.bot.world();
+        let world = world.read();
+
+        let mut found = Vec::new();
+        for offset in neighbor_offsets(false) {
+            let neighbor = pos + offset;
+            if cross_section.contains(&neighbor) || self.seen_ores.contains(&neighbor) {
+                continue;
+            }
+            let Some(kind) = vein_kind_at(&world.chunks, neighbor) else {
+                continue;
+            };
+            if is_ore(kind) {
+                self.seen_ores.insert(neighbor);
+                self.report.ores.push((neighbor, kind));
+                found.push(neighbor);
+            }
+        }
+        found
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use azalea::BlockPos;
+
+    use super::{Heading, TunnelSize};
+
+    #[test]
+    fn test_cross_section() {
+        let pos = BlockPos::new(0, 10, 0);
+        assert_eq!(
+            TunnelSize::Small.cross_section(pos, Heading::North),
+            vec![BlockPos::new(0, 11, 0), pos]
+        );
+
+        let large = TunnelSize::Large.cross_section(pos, Heading::North);
+        assert_eq!(large.len(), 9);
+        assert_eq!(large[0], BlockPos::new(-1, 12, 0));
+        assert!(large.iter().all(|block| block.z == 0));
+        assert!(
+            TunnelSize::Large
+                .cross_section(pos, Heading::East)
+                .iter()
+                .all(|block| block.x == 0)
+        );
+    }
+}
@@ -0,0 +1,232 @@
+//! Making room when the inventory fills up in the middle of mining, by
+//! throwing out junk or putting things in a chest.
+
+use azalea::ecs::prelude::*;
+use azalea::inventory::operations::{ClickOperation, ThrowClick};
+use azalea::inventory::{ContainerClickEvent, Inventory, ItemStack, components};
+use azalea::pathfinder::goals::BlockPosGoal;
+use azalea::registry::Item;
+use azalea::{BlockPos, Client};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tracing::{info, warn};
+
+use crate::commands::args::registry_name;
+use crate::containers::{ContainerError, ContainerExtrasClientExt, DepositFilter};
+use crate::mining::{StopPathfindingOnDrop, goto_blocks};
+
+/// What to do when the inventory fills up while mining.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FullInventoryAction {
+    /// Stop mining.
+    #[default]
+    Stop,
+    /// Throw out the items in `junk`.
+    DropJunk,
+    /// Put everything but tools and food in a chest, then come back.
+    Deposit,
+}
+
+/// Makes room in the inventory when it fills up while mining. Without this on
+/// the bot, mining just stops.
+#[derive(Component, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FullInventoryPolicy {
+    pub action: FullInventoryAction,
+    /// Items thrown out by [`FullInventoryAction::DropJunk`], by name. They
+    /// aren't picked up when collecting drops either.
+    pub junk: Vec<String>,
+    /// Items never deposited, by name. Tools, armor and food are always kept.
+    pub keep: Vec<String>,
+    /// The chests to deposit into, in order, moving on to the next one when
+    /// one is full. Otherwise the closest chests and barrels are used.
+    pub chests: Vec<[i32; 3]>,
+    /// How far away the closest chests can be.
+    pub chest_search_radius: f64,
+}
+
+impl Default for FullInventoryPolicy {
+    fn default() -> Self {
+        Self {
+            action: FullInventoryAction::default(),
+            junk: [
+                "cobblestone",
+                "cobbled_deepslate",
+                "dirt",
+                "gravel",
+                "andesite",
+                "diorite",
+                "granite",
+                "tuff",
+                "netherrack",
+            ]
+            .map(str::to_owned)
+            .to_vec(),
+            keep: ["torch", "totem_of_undying"].map(str::to_owned).to_vec(),
+            chests: Vec::new(),
+            chest_search_radius: 64.,
+        }
+    }
+}
+
+impl FullInventoryPolicy {
+    /// Whether an item is junk that is thrown out instead of kept.
+    pub fn is_junk(&self, item: &ItemStack) -> bool {
+        self.action == FullInventoryAction::DropJunk && self.junk.contains(&item_name(item))
+    }
+}
+
+#[derive(Debug, Clone, Error)]
+pub enum UnloadError {
+    #[error("the inventory is full")]
+    Full,
+    #[error("the inventory is full of things that aren't junk")]
+    NoJunk,
+    #[error("the inventory is full and there's no chest nearby")]
+    NoChest,
+    #[error("the inventory is full and so are the chests that could be reached")]
+    ChestsFull,
+    #[error("could not get back to {0} after unloading")]
+    CantReturn(BlockPos),
+}
+
+/// Whether no slot in the player's inventory has room for what's being
+/// mined, see [`has_room`].
+pub fn is_inventory_full(bot: &Client, mining: Option<Item>) -> bool {
+    let menu = bot.menu();
+    !menu.slots()[menu.player_slots_range()]
+        .iter()
+        .any(|item| has_room(item, mining))
+}
+
+/// Whether a slot is empty, or has less than a full stack of the item being
+/// mined. When that isn't known, any stack that isn't full has room.
+pub fn has_room(item: &ItemStack, mining: Option<Item>) -> bool {
+    let ItemStack::Present(item_data) = item else {
+        return true;
+    };
+    let max_stack_size = item_data
+        .get_component::<components::MaxStackSize>()
+        .map(|max| max.count)
+        .unwrap_or(1);
+    item_data.count < max_stack_size && mining.is_none_or(|mining| mining == item_data.kind)
+}
+
+/// Makes room if the inventory is full, following the bot's
+/// [`FullInventoryPolicy`]. An error means mining should stop.
+pub(crate) async fn make_room(bot: &Client, mining: Option<Item>) -> Result<(), UnloadError> {
+    if !is_inventory_full(bot, mining) {
+        return Ok(());
+    }
+    let policy = bot
+        .get_component::<FullInventoryPolicy>()
+        .unwrap_or_default();
+    info!("inventory is full, {:?}", policy.action);
+    match policy.action {
+        FullInventoryAction::Stop => Err(UnloadError::Full),
+        FullInventoryAction::DropJunk => drop_junk(bot, &policy).await,
+        FullInventoryAction::Deposit => {
+            let _guard = StopPathfindingOnDrop(bot);
+            let return_to = bot.position().to_block_pos_floor();
+            deposit(bot, &policy, mining).await?;
+            goto_blocks(bot, &[return_to], BlockPosGoal)
+                .await
+                .map_err(|_| UnloadError::CantReturn(return_to))
+        }
+    }
+}
+
+async fn drop_junk(bot: &Client, policy: &FullInventoryPolicy) -> Result<(), UnloadError> {
+    let inventory = bot.component::<Inventory>();
+    let menu = &inventory.inventory_menu;
+    let junk_slots: Vec<usize> = menu
+        .player_slots_range()
+        .filter(|slot| menu.slot(*slot).is_some_and(|item| policy.is_junk(item)))
+        .collect();
+    if junk_slots.is_empty() {
+        return Err(UnloadError::NoJunk);
+    }
+
+    for slot in junk_slots {
+        bot.ecs.lock().trigger(ContainerClickEvent {
+            entity: bot.entity,
+            window_id: inventory.id,
+            operation: ClickOperation::Throw(ThrowClick::All { slot: slot as u16 }),
+        });
+        bot.wait_ticks(1).await;
+    }
+    Ok(())
+}
+
+async fn deposit(
+    bot: &Client,
+    policy: &FullInventoryPolicy,
+    mining: Option<Item>,
+) -> Result<(), UnloadError> {
+    let chests: Vec<BlockPos> = if policy.chests.is_empty() {
+        bot.find_containers(policy.chest_search_radius)
+    } else {
+        policy
+            .chests
+            .iter()
+            .map(|[x, y, z]| BlockPos::new(*x, *y, *z))
+            .collect()
+    };
+    let filter = DepositFilter::AllButGear {
+        keep: policy.keep.clone(),
+    };
+    match bot.deposit_items(&chests, &filter).await {
+        Ok(_) => {}
+        Err(ContainerError::NoContainer) => return Err(UnloadError::NoChest),
+        Err(err) => {
+            warn!("could not deposit: {err}");
+            return Err(UnloadError::Full);
+        }
+    }
+    if is_inventory_full(bot, mining) {
+        return Err(UnloadError::ChestsFull);
+    }
+    Ok(())
+}
+
+fn item_name(item: &ItemStack) -> String {
+    match item {
+        ItemStack::Present(item_data) => registry_name(&item_data.kind),
+        ItemStack::Empty => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use azalea::inventory::{DataComponentPatch, ItemStack, ItemStackData};
+    use azalea::registry::Item;
+
+    use super::has_room;
+
+    fn stack(kind: Item, count: i32) -> ItemStack {
+        ItemStack::Present(ItemStackData {
+            kind,
+            count,
+            component_patch: DataComponentPatch::default(),
+        })
+    }
+
+    #[test]
+    fn test_has_room() {
+        assert!(has_room(&ItemStack::Empty, Some(Item::Cobblestone)));
+        assert!(has_room(&stack(Item::Cobblestone, 10), None));
+        assert!(has_room(
+            &stack(Item::Cobblestone, 10),
+            Some(Item::Cobblestone)
+        ));
+        // room for more cobblestone doesn't help with raw iron
+        assert!(!has_room(
+            &stack(Item::Cobblestone, 10),
+            Some(Item::RawIron)
+        ));
+        assert!(!has_room(&stack(Item::Cobblestone, 64), None));
+        assert!(!has_room(&stack(Item::EnderPearl, 16), None));
+        assert!(!has_room(&stack(Item::DiamondPickaxe, 1), None));
+    }
+}
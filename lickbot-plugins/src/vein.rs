@@ -0,0 +1,136 @@
+//! Finding connected blocks of the same kind, like an ore vein or a tree, and
+//! the order to mine them in.
+
+use std::collections::{HashSet, VecDeque};
+
+use azalea::BlockPos;
+use azalea::blocks::BlockTrait;
+use azalea::world::ChunkStorage;
+
+/// The name of a block, with deepslate ores counted as their stone variant so
+/// veins crossing into deepslate are found whole.
+pub fn vein_kind(id: &str) -> &str {
+    id.strip_prefix("deepslate_")
+        .filter(|id| id.ends_with("_ore"))
+        .unwrap_or(id)
+}
+
+/// The vein kind of the block at a position, or `None` for air and unloaded
+/// blocks.
+pub fn vein_kind_at(chunks: &ChunkStorage, pos: BlockPos) -> Option<&'static str> {
+    let block_state = chunks.get_block_state(pos)?;
+    if block_state.is_air() {
+        return None;
+    }
+    let block: Box<dyn BlockTrait> = block_state.into();
+    Some(vein_kind(block.id()))
+}
+
+/// Finds up to `max_blocks` blocks connected to `start` that are the same kind
+/// as it, closest to `start` first. Blocks only touching at an edge or corner
+/// are included if `diagonals` is set.
+pub fn find_vein(
+    chunks: &ChunkStorage,
+    start: BlockPos,
+    diagonals: bool,
+    max_blocks: usize,
+) -> Vec<BlockPos> {
+    let Some(kind) = vein_kind_at(chunks, start) else {
+        return Vec::new();
+    };
+
+    let offsets = neighbor_offsets(diagonals);
+    let mut vein = Vec::new();
+    let mut seen = HashSet::from([start]);
+    let mut to_check = VecDeque::from([start]);
+    while let Some(pos) = to_check.pop_front() {
+        if vein.len() >= max_blocks {
+            break;
+        }
+        vein.push(pos);
+
+        for offset in &offsets {
+            let neighbor = pos + *offset;
+            if seen.insert(neighbor) && vein_kind_at(chunks, neighbor) == Some(kind) {
+                to_check.push_back(neighbor);
+            }
+        }
+    }
+    vein
+}
+
+/// The offsets of the blocks touching a block, by a face or also by an edge
+/// or corner.
+pub(crate) fn neighbor_offsets(diagonals: bool) -> Vec<BlockPos> {
+    let mut offsets = Vec::new();
+    for x in -1..=1 {
+        for y in -1..=1 {
+            for z in -1..=1 {
+                let touching_faces = (x != 0) as i32 + (y != 0) as i32 + (z != 0) as i32;
+                if touching_faces == 1 || (diagonals && touching_faces > 1) {
+                    offsets.push(BlockPos::new(x, y, z));
+                }
+            }
+        }
+    }
+    offsets
+}
+
+/// Orders blocks so each one is the closest to the one before it, starting
+/// from `from`. Not the shortest route, but close enough for a vein and it
+/// keeps the bot from walking back and forth.
+pub fn plan_route(from: BlockPos, blocks: &[BlockPos]) -> Vec<BlockPos> {
+    let mut remaining = blocks.to_vec();
+    let mut route = Vec::with_capacity(blocks.len());
+    let mut current = from;
+    while !remaining.is_empty() {
+        let (closest, _) = remaining
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, pos)| pos.distance_squared_to(current))
+            .expect("remaining is not empty");
+        current = remaining.swap_remove(closest);
+        route.push(current);
+    }
+    route
+}
+
+#[cfg(test)]
+mod tests {
+    use azalea::BlockPos;
+
+    use super::{neighbor_offsets, plan_route, vein_kind};
+
+    #[test]
+    fn test_vein_kind() {
+        assert_eq!(vein_kind("deepslate_iron_ore"), "iron_ore");
+        assert_eq!(vein_kind("iron_ore"), "iron_ore");
+        assert_eq!(vein_kind("deepslate_bricks"), "deepslate_bricks");
+        assert_eq!(vein_kind("oak_log"), "oak_log");
+    }
+
+    #[test]
+    fn test_neighbor_offsets() {
+        assert_eq!(neighbor_offsets(false).len(), 6);
+        assert_eq!(neighbor_offsets(true).len(), 26);
+    }
+
+    #[test]
+    fn test_plan_route() {
+        let blocks = [
+            BlockPos::new(10, 0, 0),
+            BlockPos::new(1, 0, 0),
+            BlockPos::new(5, 0, 0),
+            BlockPos::new(2, 0, 0),
+        ];
+        assert_eq!(
+            plan_route(BlockPos::new(0, 0, 0), &blocks),
+            vec![
+                BlockPos::new(1, 0, 0),
+                BlockPos::new(2, 0, 0),
+                BlockPos::new(5, 0, 0),
+                BlockPos::new(10, 0, 0),
+            ]
+        );
+    }
+}
@@ -0,0 +1,220 @@
+use anyhow::{Result, anyhow, bail};
+use azalea::BlockPos;
+use azalea::entity::Position;
+use azalea::pathfinder::PathfinderOpts;
+use azalea::pathfinder::goals::RadiusGoal;
+use azalea::prelude::*;
+use azalea::world::InstanceName;
+use lickbot_plugins::commands::args::{Arg, ArgKind, registry_name};
+use lickbot_plugins::commands::permissions::PermissionLevel;
+use lickbot_plugins::commands::{Command, CommandContext, CommandRegistry};
+use lickbot_plugins::containers::{ContainerExtrasClientExt, DepositFilter};
+use lickbot_plugins::inventory::throw_items;
+use tracing::info;
+
+use super::spawn_task;
+use crate::State;
+
+/// How many items `!fetch` gets when no count is given.
+const DEFAULT_FETCH_COUNT: i32 = 64;
+/// How many containers `!where` lists.
+const MAX_LISTED_CONTAINERS: usize = 8;
+
+pub fn register(registry: &mut CommandRegistry<State>) {
+    registry
+        .register(
+            Command::new("deposit", deposit)
+                .description(
+                    "Puts everything but tools, armor and food in chests, or only an item, or everything over a count with keep",
+                )
+                .args([])
+                .args([
+                    Arg::new("item", ArgKind::Item),
+                    Arg::optional("keep", ArgKind::Integer),
+                ])
+                .args([
+                    Arg::new("mode", ArgKind::Word),
+                    Arg::new("keep", ArgKind::Integer),
+                ]),
+        )
+        .register(
+            Command::new("fetch", fetch)
+                .description("Takes an item out of chests, add here to have it brought to you")
+                .args([
+                    Arg::new("item", ArgKind::Item),
+                    Arg::optional("count", ArgKind::Integer),
+                ])
+                .args([
+                    Arg::new("item", ArgKind::Item),
+                    Arg::new("count", ArgKind::Integer),
+                    Arg::new("mode", ArgKind::Word),
+                ]),
+        )
+        .register(
+            Command::new("where", where_is)
+                .description("Lists the chests an item is in, out of the ones the bots have opened")
+                .permission(PermissionLevel::Public)
+                .args([Arg::new("item", ArgKind::Item)]),
+        );
+}
+
+/// The configured chests, otherwise the closest ones.
+fn storage_containers(ctx: &CommandContext<State>) -> Vec<BlockPos> {
+    let (chests, search_radius) = {
+        let config = ctx.state.config.read();
+        (config.deposit.chests.clone(), config.deposit.search_radius)
+    };
+    if chests.is_empty() {
+        ctx.bot.find_containers(search_radius)
+    } else {
+        chests
+            .iter()
+            .map(|[x, y, z]| BlockPos::new(*x, *y, *z))
+            .collect()
+    }
+}
+
+async fn deposit(ctx: CommandContext<State>) -> Result<()> {
+    let keep = ctx.args.integer("keep").unwrap_or_default();
+    if keep < 0 {
+        bail!("can't keep less than 0 items");
+    }
+    let (filter, name) = match (ctx.args.item("item"), ctx.args.word("mode")) {
+        (Some(item), _) => (
+            DepositFilter::Item { item, keep },
+            format!("deposit {}", registry_name(&item)),
+        ),
+        (None, Some("keep")) => (
+            DepositFilter::OverCount { keep },
+            format!("deposit keep {keep}"),
+        ),
+        (None, Some(mode)) => bail!("unknown mode {mode}, the only mode is keep"),
+        (None, None) => (
+            DepositFilter::AllButGear {
+                keep: ctx.state.config.read().deposit.keep.clone(),
+            },
+            "deposit".to_owned(),
+        ),
+    };
+
+    spawn_task(ctx, name, move |ctx, _| async move {
+        let containers = storage_containers(&ctx);
+        let report = ctx.bot.deposit_items(&containers, &filter).await?;
+        ctx.reply(report.to_string());
+        Ok(())
+    });
+    Ok(())
+}
+
+async fn fetch(ctx: CommandContext<State>) -> Result<()> {
+    let Some(item) = ctx.args.item("item") else {
+        return Ok(());
+    };
+    let count = ctx.args.integer("count").unwrap_or(DEFAULT_FETCH_COUNT);
+    if count <= 0 {
+        bail!("count has to be more than 0");
+    }
+    let bring = match ctx.args.word("mode") {
+        None => false,
+        Some("here") => true,
+        Some(mode) => bail!("unknown mode {mode}, the only mode is here"),
+    };
+    if bring && ctx.sender.uuid.is_none() {
+        bail!("could not find message sender");
+    }
+
+    let name = format!("fetch {count} {}", registry_name(&item));
+    spawn_task(ctx, name, move |ctx, _| async move {
+        let containers = storage_containers(&ctx);
+        let report = ctx.bot.withdraw_items(&containers, item, count).await?;
+        if !bring || report.withdrawn == 0 {
+            ctx.reply(report.to_string());
+            return Ok(());
+        }
+
+        // they could have moved while the bot was getting it
+        let sender_position = ctx
+            .sender
+            .uuid
+            .and_then(|uuid| ctx.bot.entity_by_uuid(uuid))
+            .and_then(|entity| ctx.bot.get_entity_component::<Position>(entity))
+            .ok_or_else(|| anyhow!("{report}, but could not find you"))?;
+        ctx.bot.start_goto_with_opts(
+            RadiusGoal {
+                pos: *sender_position,
+                radius: 2.,
+            },
+            PathfinderOpts::new(),
+        );
+        ctx.bot.wait_until_goto_target_reached().await;
+        ctx.bot.look_at(*sender_position);
+        let thrown = throw_items(&ctx.bot, item, report.withdrawn).await;
+        ctx.reply(format!("{report}, brought you {thrown}"));
+        Ok(())
+    });
+    Ok(())
+}
+
+async fn where_is(ctx: CommandContext<State>) -> Result<()> {
+    let Some(item) = ctx.args.item("item") else {
+        return Ok(());
+    };
+    let world = ctx.state.config.read().address.clone();
+    let dimension = ctx.bot.component::<InstanceName>().to_string();
+    let removed = ctx
+        .state
+        .containers
+        .prune(&world, &dimension, &ctx.bot.world().read().chunks);
+    if removed > 0 {
+        info!("forgot {removed} containers that are gone");
+    }
+
+    let mut locations = ctx.state.containers.find(&world, item);
+    if locations.is_empty() {
+        ctx.reply(format!(
+            "no {} in any container I've opened",
+            registry_name(&item)
+        ));
+        return Ok(());
+    }
+    // the closest ones in this dimension first
+    let position = ctx.bot.position();
+    locations.sort_by(|a, b| {
+        (a.dimension != dimension)
+            .cmp(&(b.dimension != dimension))
+            .then(
+                a.pos
+                    .center()
+                    .distance_squared_to(position)
+                    .total_cmp(&b.pos.center().distance_squared_to(position)),
+            )
+    });
+
+    let total: i32 = locations.iter().map(|location| location.count).sum();
+    let listed = locations
+        .iter()
+        .take(MAX_LISTED_CONTAINERS)
+        .map(|location| {
+            let pos = location.pos;
+            if location.dimension == dimension {
+                format!("{} {} {} ({})", pos.x, pos.y, pos.z, location.count)
+            } else {
+                format!(
+                    "{} {} {} in {} ({})",
+                    pos.x, pos.y, pos.z, location.dimension, location.count
+                )
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+    let mut reply = format!(
+        "{total} {} in {} containers: {listed}",
+        registry_name(&item),
+        locations.len()
+    );
+    if locations.len() > MAX_LISTED_CONTAINERS {
+        reply += &format!(", and {} more", locations.len() - MAX_LISTED_CONTAINERS);
+    }
+    ctx.reply(reply);
+    Ok(())
+}
@@ -0,0 +1,56 @@
+use anyhow::Result;
+use lickbot_plugins::commands::permissions::PermissionLevel;
+use lickbot_plugins::commands::{Command, CommandContext, CommandRegistry};
+
+use crate::State;
+
+pub fn register(registry: &mut CommandRegistry<State>) {
+    registry
+        .register(
+            Command::new("ping", ping)
+                .description("Replies with pong")
+                .permission(PermissionLevel::Public),
+        )
+        .register(
+            Command::new("health", health)
+                .description("Shows the bot's health")
+                .permission(PermissionLevel::Public),
+        )
+        .register(
+            Command::new("hunger", hunger)
+                .description("Shows the bot's hunger and saturation")
+                .permission(PermissionLevel::Public),
+        )
+        .register(
+            Command::new("pos", pos)
+                .alias("position")
+                .description("Shows the bot's position")
+                .permission(PermissionLevel::Public),
+        );
+}
+
+async fn ping(ctx: CommandContext<State>) -> Result<()> {
+    ctx.reply("pong!");
+    Ok(())
+}
+
+async fn health(ctx: CommandContext<State>) -> Result<()> {
+    let health = ctx.bot.health();
+    ctx.reply(format!("health: {health}"));
+    Ok(())
+}
+
+async fn hunger(ctx: CommandContext<State>) -> Result<()> {
+    let hunger = ctx.bot.hunger();
+    ctx.reply(format!(
+        "hunger: {}, saturation: {}",
+        hunger.food, hunger.saturation
+    ));
+    Ok(())
+}
+
+async fn pos(ctx: CommandContext<State>) -> Result<()> {
+    let pos = ctx.bot.position();
+    ctx.reply(format!("x: {}, y: {}, z: {}", pos.x, pos.y, pos.z));
+    Ok(())
+}
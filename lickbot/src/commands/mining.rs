@@ -0,0 +1,535 @@
+use anyhow::{Context, Result, bail};
+use azalea::prelude::*;
+use azalea::registry::{Block, Item};
+use azalea::{BlockPos, Vec3};
+use lickbot_plugins::commands::args::{Arg, ArgKind, parse_registry, registry_name};
+use lickbot_plugins::commands::permissions::PermissionLevel;
+use lickbot_plugins::commands::{Command, CommandContext, CommandError, CommandRegistry};
+use lickbot_plugins::drops::{DropOpts, DropsReport};
+use lickbot_plugins::inventory::num_items_in_slots;
+use lickbot_plugins::mining::{MiningExtrasClientExt, VeinOpts};
+use lickbot_plugins::quarry::{Cuboid, QuarryOpts};
+use lickbot_plugins::tasks::TaskHandle;
+use lickbot_plugins::tunnel::{OreMode, TunnelOpts};
+use tracing::{debug, info};
+
+use super::spawn_task;
+use crate::State;
+
+pub fn register(registry: &mut CommandRegistry<State>) {
+    registry
+        .register(
+            Command::new("mine", mine)
+                .description("Mines a nearby block and picks up what it drops")
+                // everything dropped is picked up now, but the item is still
+                // accepted so old usages and queued commands keep working
+                .args([
+                    Arg::new("block", ArgKind::Block),
+                    Arg::optional("item", ArgKind::Item),
+                ])
+                .args([Arg::new("pos", ArgKind::BlockPos)]),
+        )
+        .register(
+            Command::new("mineall", mineall)
+                .description("Keeps mining a block and picking up what it drops")
+                // the item is ignored, like for mine
+                .args([
+                    Arg::new("block", ArgKind::Block),
+                    Arg::optional("item", ArgKind::Item),
+                ]),
+        )
+        .register(
+            Command::new("vein", vein)
+                .description(
+                    "Mines a whole vein of a block, add diagonal to include blocks touching at corners",
+                )
+                .args([
+                    Arg::new("block", ArgKind::Block),
+                    Arg::optional("mode", ArgKind::Word),
+                ])
+                .args([
+                    Arg::new("pos", ArgKind::BlockPos),
+                    Arg::optional("mode", ArgKind::Word),
+                ]),
+        )
+        .register(
+            Command::new("quarry", quarry)
+                .description("Digs out the area between two corners, top layer first")
+                .args([
+                    Arg::new("from", ArgKind::BlockPos),
+                    Arg::new("to", ArgKind::BlockPos),
+                    Arg::optional("drops", ArgKind::Word),
+                ]),
+        )
+        .register(
+            Command::new("tunnel", tunnel)
+                .description("Digs a 1x2 or 3x3 tunnel and lists the ores it finds")
+                .args([
+                    Arg::new("direction", ArgKind::Word),
+                    Arg::new("length", ArgKind::Integer),
+                    Arg::optional("size", ArgKind::Word),
+                ]),
+        )
+        .register(
+            Command::new("stripmine", stripmine)
+                .alias("branchmine")
+                .description("Digs a tunnel with branches to both sides and mines the ores in the walls")
+                .args([
+                    Arg::new("direction", ArgKind::Word),
+                    Arg::new("length", ArgKind::Integer),
+                    Arg::optional("spacing", ArgKind::Integer),
+                    Arg::optional("branch_length", ArgKind::Integer),
+                ]),
+        )
+        .register(
+            Command::new("pickup", pickup)
+                .description("Picks up a nearby item")
+                .args([Arg::new("item", ArgKind::Item)]),
+        )
+        .register(
+            Command::new("collect", collect)
+                .alias("loot")
+                .description("Picks up every item on the ground nearby, like after a fight")
+                .args([Arg::optional("radius", ArgKind::Integer)]),
+        )
+        .register(
+            Command::new("stats", stats)
+                .description(
+                    "Shows what the bot has mined since it joined, or resets it if you're trusted",
+                )
+                .permission(PermissionLevel::Public)
+                .args([Arg::optional("action", ArgKind::Word)]),
+        );
+}
+
+/// Finds up to `count` blocks of the given kind, easiest to get to first.
+fn find_nearby_blocks(
+    ctx: &CommandContext<State>,
+    block: Block,
+    count: usize,
+) -> Result<Vec<BlockPos>> {
+    let search = ctx.state.config.read().mining_targets.clone();
+    let mut blocks_pos = ctx.bot.find_mining_targets(&block.into(), &search);
+    blocks_pos.truncate(count);
+    if blocks_pos.is_empty() {
+        info!("Could not find block nearby: {}", block);
+        bail!("Could not find block nearby: {}", registry_name(&block));
+    }
+    Ok(blocks_pos)
+}
+
+/// Like [`find_nearby_blocks`], but walks out to look for the block if
+/// there's none in the loaded chunks and exploring is on.
+async fn find_or_explore_blocks(
+    ctx: &CommandContext<State>,
+    block: Block,
+    count: usize,
+) -> Result<Vec<BlockPos>> {
+    let (search, explore) = {
+        let config = ctx.state.config.read();
+        (config.mining_targets.clone(), config.explore.clone())
+    };
+    let nearby = find_nearby_blocks(ctx, block, count);
+    if nearby.is_ok() || !explore.enabled {
+        return nearby;
+    }
+    let opts = explore.opts(&registry_name(&block));
+    ctx.reply(format!("no {} nearby, exploring", registry_name(&block)));
+    let mut blocks_pos = ctx
+        .bot
+        .explore_for_blocks(&block.into(), &search, &opts)
+        .await
+        .with_context(|| format!("Could not find {}", registry_name(&block)))?;
+    blocks_pos.truncate(count);
+    Ok(blocks_pos)
+}
+
+/// Counts how many of an item are in the bot's inventory.
+fn count_items(bot: &Client, item: Item) -> i32 {
+    let menu = bot.menu();
+    num_items_in_slots(&menu.slots()[menu.player_slots_range()], item)
+}
+
+async fn mine(ctx: CommandContext<State>) -> Result<()> {
+    let name = match (ctx.args.block_pos("pos"), ctx.args.block("block")) {
+        (Some(pos), _) => format!("mine {} {} {}", pos.x, pos.y, pos.z),
+        (None, Some(block)) => format!("mine {}", registry_name(&block)),
+        (None, None) => return Ok(()),
+    };
+    spawn_task(ctx, name, |ctx, _| mine_task(ctx));
+    Ok(())
+}
+
+async fn mine_task(ctx: CommandContext<State>) -> Result<()> {
+    let bot = &ctx.bot;
+
+    if let Some(pos) = ctx.args.block_pos("pos") {
+        info!("Mining at position: {:?}", pos);
+        bot.goto_and_try_mine_block(&pos).await?;
+        let drops = bot
+            .collect_drops(&[pos.center()], &DropOpts::default())
+            .await;
+        ctx.reply(format!(
+            "mined block at {} {} {}, {drops}",
+            pos.x, pos.y, pos.z
+        ));
+        return Ok(());
+    }
+
+    let Some(block) = ctx.args.block("block") else {
+        return Ok(());
+    };
+
+    let blocks_pos = find_or_explore_blocks(&ctx, block, 10).await?;
+    info!("Mining block {} at positions {:?}", block, blocks_pos);
+    bot.goto_and_try_mine_blocks(&blocks_pos).await?;
+    let drops = bot
+        .collect_drops(&mined_blocks(bot, &blocks_pos), &DropOpts::default())
+        .await;
+    ctx.reply(format!("mined 1 {}, {drops}", registry_name(&block)));
+
+    Ok(())
+}
+
+/// The centers of the blocks that have been mined, to look for drops around.
+fn mined_blocks(bot: &Client, blocks_pos: &[BlockPos]) -> Vec<Vec3> {
+    let world = bot.world();
+    let world = world.read();
+    blocks_pos
+        .iter()
+        .filter(|pos| {
+            world
+                .get_block_state(**pos)
+                .is_none_or(|block_state| block_state.is_air())
+        })
+        .map(BlockPos::center)
+        .collect()
+}
+
+async fn mineall(ctx: CommandContext<State>) -> Result<()> {
+    let Some(block) = ctx.args.block("block") else {
+        return Ok(());
+    };
+    spawn_task(
+        ctx,
+        format!("mineall {}", registry_name(&block)),
+        mineall_task,
+    );
+    Ok(())
+}
+
+async fn mineall_task(ctx: CommandContext<State>, task: TaskHandle) -> Result<()> {
+    let bot = &ctx.bot;
+    let Some(block) = ctx.args.block("block") else {
+        return Ok(());
+    };
+
+    let mut mined = 0;
+    let mut drops = DropsReport::default();
+    let summary = |mined: i32, drops: &DropsReport| {
+        format!("mined {mined} {}, {drops}", registry_name(&block))
+    };
+    // logs and the like drop themselves, ores drop something else
+    let mining = parse_registry(&registry_name(&block));
+
+    loop {
+        if let Err(err) = bot.make_room(mining).await {
+            ctx.reply(format!("{}, stopped because {err}", summary(mined, &drops)));
+            return Ok(());
+        }
+
+        // mine a block
+        let Ok(blocks_pos) = find_or_explore_blocks(&ctx, block, 10).await else {
+            ctx.reply(format!("{}, none left nearby", summary(mined, &drops)));
+            return Ok(());
+        };
+        info!("Mining block {} at positions {:?}", block, blocks_pos);
+        if let Err(err) = bot.goto_and_try_mine_blocks(&blocks_pos).await {
+            ctx.reply(summary(mined, &drops));
+            return Err(err.into());
+        }
+        mined += 1;
+        let mut origins = mined_blocks(bot, &blocks_pos);
+        task.set_progress(summary(mined, &drops));
+
+        // then, try to mine all other blocks it can reach
+        let blocks_pos = find_nearby_blocks(&ctx, block, 15).unwrap_or_default();
+
+        loop {
+            if let Err(err) = bot.make_room(mining).await {
+                ctx.reply(format!("{}, stopped because {err}", summary(mined, &drops)));
+                return Ok(());
+            }
+            if bot.mine_blocks_with_best_tool(&blocks_pos).await.is_err() {
+                break;
+            }
+            mined += 1;
+        }
+        origins.extend(mined_blocks(bot, &blocks_pos));
+        task.set_progress(summary(mined, &drops));
+
+        // then pick up everything that dropped
+        drops.merge(&bot.collect_drops(&origins, &DropOpts::default()).await);
+        task.set_progress(summary(mined, &drops));
+
+        debug!("restarting mining loop");
+
+        bot.wait_updates(1).await; // just in case lmao
+    }
+}
+
+async fn vein(ctx: CommandContext<State>) -> Result<()> {
+    let diagonals = match ctx.args.word("mode") {
+        None => false,
+        Some("diagonal") => true,
+        Some(mode) => bail!("unknown mode {mode}, the only mode is diagonal"),
+    };
+    let name = match (ctx.args.block_pos("pos"), ctx.args.block("block")) {
+        (Some(pos), _) => format!("vein {} {} {}", pos.x, pos.y, pos.z),
+        (None, Some(block)) => format!("vein {}", registry_name(&block)),
+        (None, None) => return Ok(()),
+    };
+    spawn_task(ctx, name, move |ctx, _| vein_task(ctx, diagonals));
+    Ok(())
+}
+
+async fn vein_task(ctx: CommandContext<State>, diagonals: bool) -> Result<()> {
+    let bot = &ctx.bot;
+    let start = match (ctx.args.block_pos("pos"), ctx.args.block("block")) {
+        (Some(pos), _) => pos,
+        (None, Some(block)) => find_or_explore_blocks(&ctx, block, 1).await?[0],
+        (None, None) => return Ok(()),
+    };
+
+    let opts = VeinOpts {
+        diagonals,
+        ..Default::default()
+    };
+    let report = bot.mine_vein(&start, &opts).await?;
+
+    let mut reply = format!(
+        "mined {} of {} blocks in the vein",
+        report.mined, report.found
+    );
+    if !report.skipped.is_empty() {
+        reply.push_str(&format!(", could not reach {}", report.skipped.len()));
+    }
+    if let Some(stop) = &report.stopped {
+        reply.push_str(&format!(", stopped because {stop}"));
+    }
+    if opts.collect_drops {
+        reply.push_str(&format!(", {}", report.drops));
+    }
+    ctx.reply(reply);
+    Ok(())
+}
+
+/// The most blocks `!quarry` digs, so a typo doesn't start a week long job.
+const MAX_QUARRY_VOLUME: u64 = 100_000;
+
+async fn quarry(ctx: CommandContext<State>) -> Result<()> {
+    let (Some(from), Some(to)) = (ctx.args.block_pos("from"), ctx.args.block_pos("to")) else {
+        return Ok(());
+    };
+    let collect_drops = match ctx.args.word("drops") {
+        None | Some("pickup") => true,
+        Some("ignore") => false,
+        Some(drops) => bail!("unknown drops mode {drops}, use pickup or ignore"),
+    };
+    let area = Cuboid::new(from, to);
+    match area.volume() {
+        Some(volume) if volume <= MAX_QUARRY_VOLUME => {}
+        Some(volume) => bail!("area has {volume} blocks, the most is {MAX_QUARRY_VOLUME}"),
+        None => bail!("area is too big, the most is {MAX_QUARRY_VOLUME} blocks"),
+    }
+
+    let name = format!(
+        "quarry {} {} {} {} {} {}",
+        from.x, from.y, from.z, to.x, to.y, to.z
+    );
+    spawn_task(ctx, name, move |ctx, task| {
+        quarry_task(ctx, task, area, collect_drops)
+    });
+    Ok(())
+}
+
+async fn quarry_task(
+    ctx: CommandContext<State>,
+    task: TaskHandle,
+    area: Cuboid,
+    collect_drops: bool,
+) -> Result<()> {
+    let opts = QuarryOpts { collect_drops };
+    let mut last_reported = 0;
+    let progress = ctx
+        .bot
+        .quarry(&area, &opts, |progress| {
+            let percent = progress.percent();
+            task.set_progress(format!("{percent}%, at y {}", progress.layer));
+            // every 10%
+            if percent / 10 > last_reported / 10 {
+                last_reported = percent;
+                ctx.reply(format!("quarry {percent}% done"));
+            }
+        })
+        .await;
+
+    let mut reply = format!("quarry done, mined {} blocks", progress.mined);
+    if progress.skipped > 0 {
+        reply.push_str(&format!(", could not mine {}", progress.skipped));
+    }
+    if let Some(stop) = &progress.stopped {
+        reply.push_str(&format!(", stopped because {stop}"));
+    }
+    if opts.collect_drops {
+        reply.push_str(&format!(", {}", progress.drops));
+    }
+    ctx.reply(reply);
+    Ok(())
+}
+
+/// Reads the direction and length shared by `!tunnel` and `!stripmine`.
+fn tunnel_opts(ctx: &CommandContext<State>) -> Result<TunnelOpts> {
+    let (Some(direction), Some(length)) = (ctx.args.word("direction"), ctx.args.integer("length"))
+    else {
+        bail!("missing direction or length");
+    };
+    let Ok(length) = u32::try_from(length) else {
+        bail!("length can't be negative");
+    };
+    Ok(TunnelOpts::new(direction.parse()?, length))
+}
+
+async fn tunnel(ctx: CommandContext<State>) -> Result<()> {
+    let mut opts = tunnel_opts(&ctx)?;
+    if let Some(size) = ctx.args.word("size") {
+        opts.size = size.parse()?;
+    }
+    let name = format!("tunnel {} {}", opts.heading, opts.length);
+    spawn_task(ctx, name, move |ctx, task| tunnel_task(ctx, task, opts));
+    Ok(())
+}
+
+async fn stripmine(ctx: CommandContext<State>) -> Result<()> {
+    let mut opts = tunnel_opts(&ctx)?;
+    let spacing = ctx.args.integer("spacing").unwrap_or(4);
+    let branch_length = ctx.args.integer("branch_length").unwrap_or(16);
+    let (Ok(spacing @ 1..), Ok(branch_length)) =
+        (u32::try_from(spacing), u32::try_from(branch_length))
+    else {
+        bail!("spacing has to be at least 1 and branch_length can't be negative");
+    };
+    opts.branch_every = Some(spacing);
+    opts.branch_length = branch_length;
+    opts.ores = OreMode::Mine;
+
+    let name = format!("stripmine {} {}", opts.heading, opts.length);
+    spawn_task(ctx, name, move |ctx, task| tunnel_task(ctx, task, opts));
+    Ok(())
+}
+
+async fn tunnel_task(ctx: CommandContext<State>, task: TaskHandle, opts: TunnelOpts) -> Result<()> {
+    let report = ctx
+        .bot
+        .dig_tunnel(&opts, |report| {
+            task.set_progress(format!(
+                "dug {} blocks, found {} ores",
+                report.dug,
+                report.ores.len()
+            ));
+        })
+        .await;
+
+    let mut reply = format!("dug {} blocks", report.dug);
+    if opts.ores == OreMode::Mine {
+        reply.push_str(&format!(", mined {} ores", report.ores_mined));
+    }
+    let mut ore_counts: Vec<(&str, usize)> = Vec::new();
+    for (_, kind) in &report.ores {
+        match ore_counts.iter_mut().find(|(counted, _)| counted == kind) {
+            Some((_, count)) => *count += 1,
+            None => ore_counts.push((kind, 1)),
+        }
+    }
+    if !ore_counts.is_empty() {
+        let ores: Vec<String> = ore_counts
+            .iter()
+            .map(|(kind, count)| format!("{count} {kind}"))
+            .collect();
+        reply.push_str(&format!(", found {}", ores.join(", ")));
+    }
+    if let Some(stop) = &report.stopped {
+        reply.push_str(&format!(", stopped because {stop}"));
+    }
+    ctx.reply(reply);
+    Ok(())
+}
+
+async fn pickup(ctx: CommandContext<State>) -> Result<()> {
+    let Some(item) = ctx.args.item("item") else {
+        return Ok(());
+    };
+    spawn_task(ctx, format!("pickup {}", registry_name(&item)), |ctx, _| {
+        pickup_task(ctx)
+    });
+    Ok(())
+}
+
+async fn pickup_task(ctx: CommandContext<State>) -> Result<()> {
+    let Some(item) = ctx.args.item("item") else {
+        return Ok(());
+    };
+    info!("Picking up item: {}", item);
+
+    let starting_items = count_items(&ctx.bot, item);
+    ctx.bot.pick_up_item(item).await?;
+    ctx.reply(format!(
+        "picked up {} {}",
+        count_items(&ctx.bot, item) - starting_items,
+        registry_name(&item)
+    ));
+    Ok(())
+}
+
+/// How far around the bot `!collect` looks by default.
+const DEFAULT_COLLECT_RADIUS: i32 = 16;
+
+async fn collect(ctx: CommandContext<State>) -> Result<()> {
+    let radius = ctx.args.integer("radius").unwrap_or(DEFAULT_COLLECT_RADIUS);
+    if !(1..=64).contains(&radius) {
+        bail!("radius has to be between 1 and 64");
+    }
+    spawn_task(ctx, format!("collect {radius}"), move |ctx, _| async move {
+        let opts = DropOpts {
+            radius: f64::from(radius),
+            max_distance: f64::from(radius),
+            settle_ticks: 0,
+            ..Default::default()
+        };
+        let drops = ctx.bot.collect_drops(&[ctx.bot.position()], &opts).await;
+        ctx.reply(drops.to_string());
+        Ok(())
+    });
+    Ok(())
+}
+
+async fn stats(ctx: CommandContext<State>) -> Result<()> {
+    let tracker = ctx.bot.mining_stats();
+    match ctx.args.word("action") {
+        None => ctx.reply(tracker.snapshot().to_string()),
+        Some("reset") => {
+            // anyone can look at the stats, but not wipe them
+            if ctx.sender.level < PermissionLevel::Trusted {
+                return Err(CommandError::PermissionDenied {
+                    command: "stats reset",
+                    required: PermissionLevel::Trusted,
+                }
+                .into());
+            }
+            tracker.reset();
+            ctx.reply("mining stats reset");
+        }
+        Some(action) => bail!("unknown action {action}, the only action is reset"),
+    }
+    Ok(())
+}
@@ -0,0 +1,152 @@
+mod containers;
+mod info;
+mod mining;
+mod movement;
+mod permissions;
+mod plugins;
+mod tasks;
+
+use std::sync::{Arc, LazyLock};
+
+use anyhow::Result;
+use azalea::prelude::*;
+use lickbot_plugins::commands::permissions::PermissionLevel;
+use lickbot_plugins::commands::reply::ReplyMode;
+use lickbot_plugins::commands::{CommandContext, CommandRegistry, CommandSender};
+use lickbot_plugins::mining::MiningExtrasClientExt;
+use lickbot_plugins::tasks::{SpawnMode, TaskHandle, TaskId};
+use tracing::{info, warn};
+
+use crate::State;
+use crate::config::NewTaskMode;
+
+/// Every chat command the bot understands.
+pub static COMMANDS: LazyLock<Arc<CommandRegistry<State>>> = LazyLock::new(|| {
+    let mut registry = CommandRegistry::new();
+    containers::register(&mut registry);
+    info::register(&mut registry);
+    movement::register(&mut registry);
+    mining::register(&mut registry);
+    permissions::register(&mut registry);
+    plugins::register(&mut registry);
+    tasks::register(&mut registry);
+    Arc::new(registry)
+});
+
+/// Runs a long command as a background task of the bot. Failures are
+/// replied to the sender.
+///
+/// If the bot is already doing something, it's cancelled, or with
+/// `new_tasks = "queue"` the command is queued instead and `None` is
+/// returned.
+///
+/// The command becomes the current one in the bot's queue, so it's run again
+/// if the bot reconnects before it ends. The next queued command runs after.
+pub fn spawn_task<F, Fut>(
+    ctx: CommandContext<State>,
+    name: impl Into<String>,
+    task: F,
+) -> Option<TaskId>
+where
+    F: FnOnce(CommandContext<State>, TaskHandle) -> Fut,
+    Fut: Future<Output = Result<()>> + Send + 'static,
+{
+    let mode = ctx.state.config.read().new_tasks;
+    if mode == NewTaskMode::Queue && !ctx.state.tasks.running().is_empty() {
+        let id = ctx
+            .state
+            .queue
+            .push(&ctx.input, ctx.sender.clone(), ctx.replier.mode());
+        ctx.reply(format!("queued #{id}: {}", ctx.input));
+        return None;
+    }
+
+    let name = name.into();
+    let tasks = ctx.state.tasks.clone();
+    let replier = ctx.replier.clone();
+    let queue_id = ctx
+        .state
+        .queue
+        .start(&ctx.input, ctx.sender.clone(), replier.mode());
+
+    let id = tasks.spawn(name.clone(), SpawnMode::Preempt, |handle| {
+        let task_id = handle.id;
+        let bot = ctx.bot.clone();
+        let state = ctx.state.clone();
+        let stats = bot.mining_stats();
+        let stats_before = stats.snapshot();
+        let task = task(ctx, handle);
+        let name = name.clone();
+        let replier = replier.clone();
+        async move {
+            let result = task.await;
+            if let Err(err) = &result {
+                warn!("task {name} failed: {err}");
+                replier.send(format!("{name} failed: {err}"));
+            }
+            let task_stats = stats.snapshot().since(&stats_before);
+            if !task_stats.is_empty() {
+                replier.send(format!("{name}: {task_stats}"));
+            }
+            if state.queue.finish(queue_id) {
+                tokio::spawn(run_queued(bot, state, Some(task_id)));
+            }
+            result
+        }
+    });
+    replier.send(format!("started task #{id}: {name}"));
+
+    Some(id)
+}
+
+/// Runs the commands in the bot's queue until one of them starts a task.
+///
+/// If `after` is given, waits for that task to end first so it isn't
+/// cancelled by the next one.
+pub async fn run_queued(bot: Client, state: State, after: Option<TaskId>) {
+    if let Some(id) = after {
+        state.tasks.wait(id).await;
+    }
+
+    while let Some(command) = state.queue.pop_next() {
+        info!("running queued command: {}", command.input);
+        // commands from the console have no sender to look up
+        let level = if command.reply_mode == ReplyMode::Console {
+            PermissionLevel::Owner
+        } else {
+            state
+                .permissions
+                .read()
+                .level(command.sender.name.as_deref(), command.sender.uuid)
+        };
+        let sender = CommandSender {
+            name: command.sender.name,
+            uuid: command.sender.uuid,
+            level,
+        };
+        // errors are replied to the sender
+        let _ = COMMANDS
+            .run(
+                bot.clone(),
+                state.clone(),
+                sender,
+                command.reply_mode,
+                "",
+                &command.input,
+            )
+            .await;
+        if state.queue.get().current.is_some() {
+            break;
+        }
+    }
+}
+
+/// Carries on with the bot's queue after it joins, starting with the command
+/// it was running when it disconnected.
+pub async fn resume_queue(bot: Client, state: State) {
+    if let Some(current) = state.queue.get().current {
+        info!("resuming {}", current.input);
+    }
+    state.queue.requeue_current();
+    run_queued(bot, state, None).await;
+}
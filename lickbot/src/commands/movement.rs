@@ -0,0 +1,95 @@
+use std::fmt::Display;
+
+use anyhow::{Result, anyhow};
+use azalea::BlockPos;
+use azalea::entity::Position;
+use azalea::pathfinder::PathfinderOpts;
+use azalea::pathfinder::goals::{BlockPosGoal, XZGoal, YGoal};
+use azalea::prelude::*;
+use lickbot_plugins::commands::args::{Arg, ArgKind};
+use lickbot_plugins::commands::{Command, CommandContext, CommandRegistry};
+use tracing::{error, info};
+
+use super::spawn_task;
+use crate::State;
+
+pub fn register(registry: &mut CommandRegistry<State>) {
+    registry.register(
+        Command::new("goto", goto)
+            .description("Goes to you, a y level, an x z column or a block")
+            .args([])
+            .args([Arg::new("y", ArgKind::Integer)])
+            .args([
+                Arg::new("x", ArgKind::Integer),
+                Arg::new("z", ArgKind::Integer),
+            ])
+            .args([Arg::new("pos", ArgKind::BlockPos)]),
+    );
+}
+
+#[derive(Debug, Clone, Copy)]
+enum GotoTarget {
+    Block(BlockPos),
+    Column { x: i32, z: i32 },
+    Level { y: i32 },
+}
+
+impl GotoTarget {
+    fn start(self, bot: &Client) {
+        match self {
+            GotoTarget::Block(pos) => {
+                bot.start_goto_with_opts(BlockPosGoal(pos), PathfinderOpts::new())
+            }
+            GotoTarget::Column { x, z } => {
+                bot.start_goto_with_opts(XZGoal { x, z }, PathfinderOpts::new())
+            }
+            GotoTarget::Level { y } => bot.start_goto_with_opts(YGoal { y }, PathfinderOpts::new()),
+        }
+    }
+}
+
+impl Display for GotoTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GotoTarget::Block(pos) => write!(f, "{} {} {}", pos.x, pos.y, pos.z),
+            GotoTarget::Column { x, z } => write!(f, "{x} {z}"),
+            GotoTarget::Level { y } => write!(f, "y {y}"),
+        }
+    }
+}
+
+async fn goto(ctx: CommandContext<State>) -> Result<()> {
+    let args = &ctx.args;
+
+    let target = if let Some(pos) = args.block_pos("pos") {
+        GotoTarget::Block(pos)
+    } else if let (Some(x), Some(z)) = (args.integer("x"), args.integer("z")) {
+        GotoTarget::Column { x, z }
+    } else if let Some(y) = args.integer("y") {
+        GotoTarget::Level { y }
+    } else {
+        let error_fn = || {
+            error!("Got !goto, could not find sender");
+            anyhow!("could not find message sender")
+        };
+        let uuid = ctx.sender.uuid.ok_or_else(error_fn)?;
+        let entity = ctx.bot.entity_by_uuid(uuid).ok_or_else(error_fn)?;
+        let position = ctx
+            .bot
+            .get_entity_component::<Position>(entity)
+            .ok_or_else(error_fn)?;
+
+        let sender = ctx.sender.name.as_deref().ok_or_else(error_fn)?;
+        info!("going to location of {}", sender);
+        GotoTarget::Block(position.into())
+    };
+
+    spawn_task(ctx, format!("goto {target}"), move |ctx, _| async move {
+        target.start(&ctx.bot);
+        ctx.reply(format!("going to {target}"));
+        ctx.bot.wait_until_goto_target_reached().await;
+        ctx.reply(format!("arrived at {target}"));
+        Ok(())
+    });
+    Ok(())
+}
@@ -0,0 +1,102 @@
+use anyhow::{Result, bail};
+use azalea::prelude::*;
+use lickbot_plugins::commands::args::{Arg, ArgKind};
+use lickbot_plugins::commands::permissions::PermissionLevel;
+use lickbot_plugins::commands::{Command, CommandContext, CommandRegistry};
+use tracing::info;
+use uuid::Uuid;
+
+use crate::State;
+
+pub fn register(registry: &mut CommandRegistry<State>) {
+    registry
+        .register(
+            Command::new("trust", trust)
+                .description("Lets a player use the bot")
+                .permission(PermissionLevel::Owner)
+                .args([Arg::new("player", ArgKind::Player)]),
+        )
+        .register(
+            Command::new("untrust", untrust)
+                .description("Stops a player from using the bot")
+                .permission(PermissionLevel::Owner)
+                .args([Arg::new("player", ArgKind::Player)]),
+        )
+        .register(
+            Command::new("permission", permission)
+                .alias("perm")
+                .description("Shows the permission level of you or another player")
+                .permission(PermissionLevel::Public)
+                .args([Arg::optional("player", ArgKind::Player)]),
+        );
+}
+
+/// Finds the uuid of an online player from the tab list.
+fn find_player_uuid(bot: &Client, username: &str) -> Option<Uuid> {
+    bot.tab_list()
+        .into_iter()
+        .find(|(_, info)| info.profile.name.eq_ignore_ascii_case(username))
+        .map(|(uuid, _)| uuid)
+}
+
+async fn trust(ctx: CommandContext<State>) -> Result<()> {
+    let Some(player) = ctx.args.player("player") else {
+        return Ok(());
+    };
+    let uuid = find_player_uuid(&ctx.bot, player);
+
+    {
+        let mut permissions = ctx.state.permissions.write();
+        if permissions.level(Some(player), uuid) == PermissionLevel::Owner {
+            bail!("{player} is already an owner");
+        }
+        permissions.set(player, uuid, PermissionLevel::Trusted);
+        permissions.save(&ctx.state.config.read().permissions_file)?;
+    }
+
+    info!("{player} is now trusted");
+    ctx.reply(format!("{player} is now trusted"));
+    Ok(())
+}
+
+async fn untrust(ctx: CommandContext<State>) -> Result<()> {
+    let Some(player) = ctx.args.player("player") else {
+        return Ok(());
+    };
+    let uuid = find_player_uuid(&ctx.bot, player);
+
+    {
+        let mut permissions = ctx.state.permissions.write();
+        if permissions.level(Some(player), uuid) == PermissionLevel::Owner {
+            bail!(
+                "Owners can only be removed in {}",
+                ctx.state.config.read().permissions_file.display()
+            );
+        }
+        permissions.remove(player, uuid);
+        permissions.save(&ctx.state.config.read().permissions_file)?;
+    }
+
+    info!("{player} is no longer trusted");
+    ctx.reply(format!("{player} is no longer trusted"));
+    Ok(())
+}
+
+async fn permission(ctx: CommandContext<State>) -> Result<()> {
+    let (player, level) = match ctx.args.player("player") {
+        Some(player) => {
+            let uuid = find_player_uuid(&ctx.bot, player);
+            (
+                player,
+                ctx.state.permissions.read().level(Some(player), uuid),
+            )
+        }
+        None => (
+            ctx.sender.name.as_deref().unwrap_or("you"),
+            ctx.sender.level,
+        ),
+    };
+
+    ctx.reply(format!("{player} is {level}"));
+    Ok(())
+}
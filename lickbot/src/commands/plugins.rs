@@ -0,0 +1,118 @@
+use anyhow::{Result, bail};
+use lickbot_plugins::commands::args::{Arg, ArgKind};
+use lickbot_plugins::commands::{Command, CommandContext, CommandRegistry};
+use lickbot_plugins::toggle::{PluginState, ToggleError, TogglePlugin};
+use serde_json::Value;
+use tracing::info;
+
+use crate::State;
+use crate::plugins::{PLUGINS, apply_plugins};
+
+pub fn register(registry: &mut CommandRegistry<State>) {
+    registry.register(
+        Command::new("plugin", plugin)
+            .alias("plugins")
+            .description(
+                "Lists plugins, shows one, turns it on or off, or sets an option, \
+                 e.g. autokill on or autokill.switch_weapon false",
+            )
+            .args([])
+            .args([Arg::new("plugin", ArgKind::Word)])
+            .args([
+                Arg::new("plugin", ArgKind::Word),
+                Arg::new("value", ArgKind::Word),
+            ])
+            .args([
+                Arg::new("plugin", ArgKind::Word),
+                Arg::new("option", ArgKind::Word),
+                Arg::new("value", ArgKind::Word),
+            ]),
+    );
+}
+
+fn find_plugin(name: &str) -> Result<&'static TogglePlugin> {
+    Ok(PLUGINS
+        .get(name)
+        .ok_or_else(|| ToggleError::UnknownPlugin(name.to_owned()))?)
+}
+
+/// Describes a plugin and its options, e.g. `autoeat: on, hunger_threshold=18`.
+fn describe(plugin: &TogglePlugin, state: &PluginState) -> String {
+    let mut description = format!(
+        "{}: {}",
+        plugin.name,
+        if state.enabled { "on" } else { "off" }
+    );
+    if let Value::Object(options) = plugin.options(state) {
+        for (option, value) in options {
+            let value = match value {
+                Value::String(value) => value,
+                value => value.to_string(),
+            };
+            description.push_str(&format!(", {option}={value}"));
+        }
+    }
+    description
+}
+
+async fn plugin(ctx: CommandContext<State>) -> Result<()> {
+    let name = ctx.args.word("plugin");
+    let option = ctx.args.word("option");
+    let value = ctx.args.word("value");
+
+    match (name, option, value) {
+        (None, _, _) | (Some("list"), None, None) => list(&ctx),
+        (Some(name), Some(option), Some(value)) => set_option(&ctx, name, option, value),
+        (Some(name), None, Some(value)) => match name.split_once('.') {
+            Some((name, option)) => set_option(&ctx, name, option, value),
+            None => toggle(&ctx, name, value),
+        },
+        (Some(name), _, _) => {
+            let plugin = find_plugin(name)?;
+            ctx.reply(describe(plugin, &ctx.state.plugins.read().get(plugin.name)));
+            Ok(())
+        }
+    }
+}
+
+fn list(ctx: &CommandContext<State>) -> Result<()> {
+    let states = ctx.state.plugins.read();
+    let plugins: Vec<String> = PLUGINS
+        .plugins()
+        .map(|plugin| {
+            let enabled = states.get(plugin.name).enabled;
+            format!("{} ({})", plugin.name, if enabled { "on" } else { "off" })
+        })
+        .collect();
+    ctx.reply(format!("plugins: {}", plugins.join(", ")));
+    Ok(())
+}
+
+fn toggle(ctx: &CommandContext<State>, name: &str, value: &str) -> Result<()> {
+    let plugin = find_plugin(name)?;
+    let enabled = match value {
+        "on" | "true" => true,
+        "off" | "false" => false,
+        _ => bail!("Expected on or off, got {value}"),
+    };
+
+    ctx.state.plugins.write().get_mut(plugin.name).enabled = enabled;
+    apply_plugins(&ctx.bot, &ctx.state);
+
+    info!("{} is now {value}", plugin.name);
+    ctx.reply(describe(plugin, &ctx.state.plugins.read().get(plugin.name)));
+    Ok(())
+}
+
+fn set_option(ctx: &CommandContext<State>, name: &str, option: &str, value: &str) -> Result<()> {
+    let plugin = find_plugin(name)?;
+    {
+        let mut states = ctx.state.plugins.write();
+        plugin.set_option(states.get_mut(plugin.name), option, value)?;
+    }
+    apply_plugins(&ctx.bot, &ctx.state);
+
+    info!("set {}.{option} to {value}", plugin.name);
+    ctx.reply(describe(plugin, &ctx.state.plugins.read().get(plugin.name)));
+    Ok(())
+}
@@ -0,0 +1,170 @@
+use anyhow::{Result, bail};
+use azalea::prelude::*;
+use lickbot_plugins::commands::args::{Arg, ArgKind};
+use lickbot_plugins::commands::permissions::PermissionLevel;
+use lickbot_plugins::commands::{Command, CommandContext, CommandError, CommandRegistry};
+use lickbot_plugins::task_queue::QueuedCommand;
+use lickbot_plugins::tasks::TaskInfo;
+
+use super::run_queued;
+use crate::State;
+
+pub fn register(registry: &mut CommandRegistry<State>) {
+    registry
+        .register(
+            Command::new("tasks", tasks)
+                .description("Lists what the bot is doing and has done recently")
+                .permission(PermissionLevel::Public),
+        )
+        .register(
+            Command::new("cancel", cancel)
+                .description("Cancels a task")
+                .args([Arg::new("id", ArgKind::Integer)]),
+        )
+        .register(
+            Command::new("stop", stop)
+                .description("Cancels every task, clears the queue and stops moving"),
+        )
+        .register(
+            Command::new("queue", queue)
+                .description(
+                    "Shows the queue, or adds a command to run after the others if you're trusted",
+                )
+                .permission(PermissionLevel::Public)
+                .args([])
+                .args([Arg::new("command", ArgKind::Text)]),
+        )
+        .register(
+            Command::new("unqueue", unqueue)
+                .description("Removes a command from the queue")
+                .args([Arg::new("id", ArgKind::Integer)]),
+        );
+}
+
+fn format_task(task: &TaskInfo) -> String {
+    let mut line = format!(
+        "#{} {}: {} ({}s)",
+        task.id,
+        task.name,
+        task.status,
+        task.elapsed().as_secs()
+    );
+    if let Some(progress) = &task.progress {
+        line.push_str(&format!(", {progress}"));
+    }
+    line
+}
+
+async fn tasks(ctx: CommandContext<State>) -> Result<()> {
+    let tasks = ctx.state.tasks.tasks();
+    if tasks.is_empty() {
+        ctx.reply("no tasks");
+        return Ok(());
+    }
+
+    for task in &tasks {
+        ctx.reply(format_task(task));
+    }
+    Ok(())
+}
+
+async fn cancel(ctx: CommandContext<State>) -> Result<()> {
+    let Some(id) = ctx.args.integer("id") else {
+        return Ok(());
+    };
+    let Ok(id) = id.try_into() else {
+        bail!("No running task #{id}");
+    };
+    if !ctx.state.tasks.cancel(id) {
+        bail!("No running task #{id}");
+    }
+
+    ctx.bot.stop_pathfinding();
+    ctx.reply(format!("cancelled task #{id}"));
+
+    // carry on with the queue
+    if ctx.state.tasks.running().is_empty() {
+        ctx.state.queue.take_current();
+        tokio::spawn(run_queued(ctx.bot.clone(), ctx.state.clone(), None));
+    }
+    Ok(())
+}
+
+async fn stop(ctx: CommandContext<State>) -> Result<()> {
+    let cancelled = ctx.state.tasks.cancel_all();
+    let cleared = ctx.state.queue.clear();
+    ctx.bot.stop_pathfinding();
+    ctx.reply(format!(
+        "stopped, cancelled {cancelled} tasks and cleared {cleared} queued commands"
+    ));
+    Ok(())
+}
+
+fn format_queued(command: &QueuedCommand) -> String {
+    format!("#{} {}", command.id, command.input)
+}
+
+async fn queue(ctx: CommandContext<State>) -> Result<()> {
+    let Some(input) = ctx.args.text("command") else {
+        let queue = ctx.state.queue.get();
+        let current = queue
+            .current
+            .as_ref()
+            .map(format_queued)
+            .unwrap_or_else(|| "nothing".to_owned());
+        let queued: Vec<String> = queue.queued.iter().map(format_queued).collect();
+        if queued.is_empty() {
+            ctx.reply(format!("running {current}, nothing queued"));
+        } else {
+            ctx.reply(format!("running {current}, then {}", queued.join(", ")));
+        }
+        return Ok(());
+    };
+
+    // anyone can look at the queue, but not add to it
+    if ctx.sender.level < PermissionLevel::Trusted {
+        return Err(CommandError::PermissionDenied {
+            command: "queue",
+            required: PermissionLevel::Trusted,
+        }
+        .into());
+    }
+    let input = input.strip_prefix(&ctx.prefix).unwrap_or(input);
+    let name = input.split_whitespace().next().unwrap_or_default();
+    let Some(command) = ctx.registry.get(name) else {
+        bail!("Unknown command: {name}");
+    };
+    if ctx.sender.level < command.permission {
+        return Err(CommandError::PermissionDenied {
+            command: command.name,
+            required: command.permission,
+        }
+        .into());
+    }
+
+    let id = ctx
+        .state
+        .queue
+        .push(input, ctx.sender.clone(), ctx.replier.mode());
+    ctx.reply(format!("queued #{id}: {input}"));
+
+    if ctx.state.queue.get().current.is_none() && ctx.state.tasks.running().is_empty() {
+        tokio::spawn(run_queued(ctx.bot.clone(), ctx.state.clone(), None));
+    }
+    Ok(())
+}
+
+async fn unqueue(ctx: CommandContext<State>) -> Result<()> {
+    let Some(id) = ctx.args.integer("id") else {
+        return Ok(());
+    };
+    let Ok(id) = id.try_into() else {
+        bail!("Nothing queued with id #{id}");
+    };
+    if !ctx.state.queue.remove(id) {
+        bail!("Nothing queued with id #{id}");
+    }
+
+    ctx.reply(format!("removed #{id} from the queue"));
+    Ok(())
+}
@@ -0,0 +1,603 @@
+use std::collections::HashSet;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::{Context, Result, bail};
+use clap::Parser;
+use lickbot_plugins::commands::reply::ReplyMode;
+use lickbot_plugins::explore::{ExploreOpts, ore_y_level};
+use lickbot_plugins::targets::TargetSearch;
+use lickbot_plugins::toggle::{PluginState, PluginStates};
+use lickbot_plugins::tools::{ToolPolicy, ToolProtection};
+use lickbot_plugins::unload::FullInventoryPolicy;
+use serde::{Deserialize, Serialize};
+
+/// Command line arguments. Anything given here overrides the config file.
+#[derive(Debug, Default, Parser)]
+#[command(version, about = "mine craft bot")]
+pub struct Cli {
+    /// Path to the config file.
+    #[arg(short, long, default_value = "lickbot.toml")]
+    pub config: PathBuf,
+    /// Address of the server to join, e.g. `localhost:25565`.
+    #[arg(short, long)]
+    pub address: Option<String>,
+    /// Username of an offline account to join with. Can be given more than once.
+    #[arg(short = 'u', long = "account")]
+    pub accounts: Vec<String>,
+    /// Seconds to wait between each account joining.
+    #[arg(long)]
+    pub join_delay: Option<u64>,
+    /// Prefix of chat commands.
+    #[arg(long)]
+    pub prefix: Option<String>,
+    /// Log filter, e.g. `debug` or `lickbot=debug,info`.
+    #[arg(long)]
+    pub log_level: Option<String>,
+    /// Don't read commands from stdin.
+    #[arg(long)]
+    pub no_console: bool,
+}
+
+impl Cli {
+    /// Overrides values in the config with the ones given on the command line.
+    pub fn apply(&self, config: &mut Config) {
+        if let Some(address) = &self.address {
+            config.address = address.clone();
+        }
+        if !self.accounts.is_empty() {
+            config.accounts = self
+                .accounts
+                .iter()
+                .map(|username| AccountConfig {
+                    username: username.clone(),
+                    plugins: None,
+                })
+                .collect();
+        }
+        if let Some(join_delay) = self.join_delay {
+            config.join_delay_secs = join_delay;
+        }
+        if let Some(prefix) = &self.prefix {
+            config.command_prefix = prefix.clone();
+        }
+        if let Some(log_level) = &self.log_level {
+            config.log_level = log_level.clone();
+        }
+        if self.no_console {
+            config.console.enabled = false;
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Address of the server to join.
+    pub address: String,
+    /// Offline accounts to join with.
+    pub accounts: Vec<AccountConfig>,
+    /// Seconds to wait between each account joining.
+    pub join_delay_secs: u64,
+    /// View distance sent to the server, in chunks.
+    pub view_distance: u8,
+    pub pathfinder_debug_particles: bool,
+    pub command_prefix: String,
+    /// Whether command replies are sent in public chat or whispered to the
+    /// sender.
+    pub reply_mode: ReplyMode,
+    /// What a command that starts a task does while another task is running.
+    pub new_tasks: NewTaskMode,
+    /// Log filter, used unless `RUST_LOG` is set.
+    pub log_level: String,
+    pub permissions_file: PathBuf,
+    /// Where the task queue of each bot is saved.
+    pub tasks_file: PathBuf,
+    /// Where what's in the containers the bots opened is saved.
+    pub containers_file: PathBuf,
+    /// Plugins enabled for every bot, unless overridden for an account.
+    pub plugins: PluginsConfig,
+    pub auto_eat: AutoEatConfig,
+    /// Keeps tools from breaking while mining.
+    pub tool_protection: ToolProtection,
+    /// Which enchantments tools are picked with for each block.
+    pub tool_policy: ToolPolicy,
+    /// What mining does when the inventory fills up.
+    pub full_inventory: FullInventoryPolicy,
+    /// Which blocks are looked at when picking what to mine.
+    pub mining_targets: TargetSearch,
+    /// Looking further away when there's nothing to mine nearby.
+    pub explore: ExploreConfig,
+    /// Where `!deposit` puts things and `!fetch` takes them from.
+    pub deposit: DepositConfig,
+    /// Chat messages containing any of these aren't printed.
+    pub chat_filters: Vec<String>,
+    pub console: ConsoleConfig,
+    pub reconnect: ReconnectConfig,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            address: "localhost:25555".to_owned(),
+            accounts: vec![AccountConfig {
+                username: "lickbot".to_owned(),
+                plugins: None,
+            }],
+            join_delay_secs: 5,
+            view_distance: 32,
+            pathfinder_debug_particles: true,
+            command_prefix: "!".to_owned(),
+            reply_mode: ReplyMode::Chat,
+            new_tasks: NewTaskMode::default(),
+            log_level: "info".to_owned(),
+            permissions_file: PathBuf::from("permissions.json"),
+            tasks_file: PathBuf::from("tasks.json"),
+            containers_file: PathBuf::from("containers.json"),
+            plugins: PluginsConfig::default(),
+            auto_eat: AutoEatConfig::default(),
+            tool_protection: ToolProtection::default(),
+            tool_policy: ToolPolicy::default(),
+            full_inventory: FullInventoryPolicy::default(),
+            mining_targets: TargetSearch::default(),
+            explore: ExploreConfig::default(),
+            deposit: DepositConfig::default(),
+            chat_filters: vec![
+                "The particle was not visible for anybody".to_owned(),
+                "Displaying particle minecraft:dust".to_owned(),
+            ],
+            console: ConsoleConfig::default(),
+            reconnect: ReconnectConfig::default(),
+        }
+    }
+}
+
+impl Config {
+    /// Loads the config from a toml file. A missing file gives the default
+    /// config.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        match fs::read_to_string(path) {
+            Ok(contents) => Self::parse(&contents)
+                .with_context(|| format!("invalid config in {}", path.display())),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(err).with_context(|| format!("could not read {}", path.display())),
+        }
+    }
+
+    pub fn parse(contents: &str) -> Result<Self> {
+        Ok(toml::from_str(contents)?)
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if self.address.is_empty() {
+            bail!("address can't be empty");
+        }
+        if self.accounts.is_empty() {
+            bail!("at least one account is needed");
+        }
+        let mut usernames = HashSet::new();
+        for account in &self.accounts {
+            if !usernames.insert(account.username.to_lowercase()) {
+                bail!("account {} is listed more than once", account.username);
+            }
+        }
+        if self.command_prefix.is_empty() || self.command_prefix.contains(char::is_whitespace) {
+            bail!("command_prefix can't be empty or contain spaces");
+        }
+        if self.reply_mode == ReplyMode::Console {
+            bail!("reply_mode has to be chat or whisper");
+        }
+        if !(2..=32).contains(&self.view_distance) {
+            bail!("view_distance has to be between 2 and 32");
+        }
+        if self.auto_eat.hunger_threshold > 20 {
+            bail!("auto_eat.hunger_threshold can't be more than 20");
+        }
+        if self.full_inventory.chest_search_radius <= 0. {
+            bail!("full_inventory.chest_search_radius has to be more than 0");
+        }
+        if self.mining_targets.candidates == 0 {
+            bail!("mining_targets.candidates can't be 0");
+        }
+        if self.mining_targets.radius <= 0. {
+            bail!("mining_targets.radius has to be more than 0");
+        }
+        if self.explore.step <= 0 {
+            bail!("explore.step has to be more than 0");
+        }
+        if self.deposit.chests.is_empty() && self.deposit.search_radius <= 0. {
+            bail!("deposit.search_radius has to be more than 0");
+        }
+        self.reconnect.validate()?;
+
+        Ok(())
+    }
+
+    pub fn join_delay(&self) -> Duration {
+        Duration::from_secs(self.join_delay_secs)
+    }
+
+    /// Names of the settings that changed but are only read when the swarm
+    /// starts.
+    pub fn changes_needing_restart(&self, new: &Config) -> Vec<&'static str> {
+        let mut changed = Vec::new();
+        if self.address != new.address {
+            changed.push("address");
+        }
+        if !self
+            .accounts
+            .iter()
+            .map(|account| &account.username)
+            .eq(new.accounts.iter().map(|account| &account.username))
+        {
+            changed.push("accounts");
+        }
+        if self.join_delay_secs != new.join_delay_secs {
+            changed.push("join_delay_secs");
+        }
+        if self.log_level != new.log_level {
+            changed.push("log_level");
+        }
+        if self.permissions_file != new.permissions_file {
+            changed.push("permissions_file");
+        }
+        if self.tasks_file != new.tasks_file {
+            changed.push("tasks_file");
+        }
+        if self.containers_file != new.containers_file {
+            changed.push("containers_file");
+        }
+        if self.console != new.console {
+            changed.push("console");
+        }
+        changed
+    }
+
+    /// The state of the toggleable plugins of an account before any commands
+    /// change them.
+    pub fn plugin_states(&self, username: &str) -> PluginStates {
+        let plugins = self.plugins_for(username);
+        let mut states = PluginStates::new();
+        states.set(
+            "autoeat",
+            PluginState {
+                enabled: plugins.auto_eat,
+                options: serde_json::to_value(&self.auto_eat).ok(),
+            },
+        );
+        states.set(
+            "toolprotection",
+            PluginState {
+                enabled: plugins.tool_protection,
+                options: serde_json::to_value(&self.tool_protection).ok(),
+            },
+        );
+        states.set(
+            "toolpolicy",
+            PluginState {
+                enabled: plugins.tool_policy,
+                options: serde_json::to_value(&self.tool_policy).ok(),
+            },
+        );
+        states.set(
+            "fullinventory",
+            PluginState {
+                enabled: plugins.full_inventory,
+                options: serde_json::to_value(&self.full_inventory).ok(),
+            },
+        );
+        for (name, enabled) in [
+            ("autokill", plugins.auto_kill),
+            ("autolook", plugins.auto_look),
+            ("autototem", plugins.auto_totem),
+        ] {
+            states.set(
+                name,
+                PluginState {
+                    enabled,
+                    options: None,
+                },
+            );
+        }
+        states
+    }
+
+    /// The plugins that should be enabled for an account.
+    pub fn plugins_for(&self, username: &str) -> &PluginsConfig {
+        self.accounts
+            .iter()
+            .find(|account| account.username.eq_ignore_ascii_case(username))
+            .and_then(|account| account.plugins.as_ref())
+            .unwrap_or(&self.plugins)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AccountConfig {
+    pub username: String,
+    /// Replaces the default plugins for this account.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub plugins: Option<PluginsConfig>,
+}
+
+/// Which plugins from `lickbot-plugins` are enabled.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PluginsConfig {
+    pub auto_eat: bool,
+    pub auto_kill: bool,
+    pub auto_look: bool,
+    pub auto_totem: bool,
+    pub tool_protection: bool,
+    pub tool_policy: bool,
+    pub full_inventory: bool,
+}
+
+impl Default for PluginsConfig {
+    fn default() -> Self {
+        Self {
+            auto_eat: true,
+            auto_kill: false,
+            auto_look: true,
+            auto_totem: true,
+            tool_protection: true,
+            tool_policy: true,
+            full_inventory: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AutoEatConfig {
+    /// Food level below which the bot eats, out of 20.
+    pub hunger_threshold: u32,
+}
+
+impl Default for AutoEatConfig {
+    fn default() -> Self {
+        Self {
+            hunger_threshold: 18,
+        }
+    }
+}
+
+/// What a command that starts a task does while another task is running.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NewTaskMode {
+    /// Cancel the running task.
+    #[default]
+    Preempt,
+    /// Queue the command to run after the others.
+    Queue,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExploreConfig {
+    /// Whether mining commands walk out to look for blocks when there are
+    /// none in the loaded chunks.
+    pub enabled: bool,
+    /// How far apart the places the bot walks to are, in blocks.
+    pub step: i32,
+    /// How far from where it started the bot goes, in blocks.
+    pub max_radius: i32,
+    pub time_budget_secs: u64,
+    /// Whether ores are looked for at the y level they're most common at.
+    pub ore_levels: bool,
+}
+
+impl Default for ExploreConfig {
+    fn default() -> Self {
+        let opts = ExploreOpts::default();
+        Self {
+            enabled: true,
+            step: opts.step,
+            max_radius: opts.max_radius,
+            time_budget_secs: opts.time_budget.as_secs(),
+            ore_levels: true,
+        }
+    }
+}
+
+impl ExploreConfig {
+    /// Options for exploring for a block, by its name.
+    pub fn opts(&self, block: &str) -> ExploreOpts {
+        ExploreOpts {
+            step: self.step,
+            max_radius: self.max_radius,
+            time_budget: Duration::from_secs(self.time_budget_secs),
+            y_level: if self.ore_levels {
+                ore_y_level(block)
+            } else {
+                None
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DepositConfig {
+    /// The chests to deposit into and fetch from, in order. Otherwise the
+    /// closest chests and barrels are used.
+    pub chests: Vec<[i32; 3]>,
+    /// How far away the closest chests can be.
+    pub search_radius: f64,
+    /// Items never deposited by name, besides tools, armor and food.
+    pub keep: Vec<String>,
+}
+
+impl Default for DepositConfig {
+    fn default() -> Self {
+        Self {
+            chests: Vec::new(),
+            search_radius: 64.,
+            keep: vec!["torch".to_owned(), "totem_of_undying".to_owned()],
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConsoleConfig {
+    /// Whether commands are read from stdin.
+    pub enabled: bool,
+    pub history_file: PathBuf,
+}
+
+impl Default for ConsoleConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            history_file: PathBuf::from(".lickbot_history"),
+        }
+    }
+}
+
+/// When and how often bots rejoin after being disconnected.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReconnectConfig {
+    pub enabled: bool,
+    /// Seconds to wait before the first attempt.
+    pub initial_delay_secs: u64,
+    /// The delay is multiplied by this after each failed attempt.
+    pub multiplier: f64,
+    pub max_delay_secs: u64,
+    /// How much the delay is randomly changed by, e.g. 0.2 for up to 20% more
+    /// or less, so bots don't all rejoin at once.
+    pub jitter: f64,
+    /// Attempts before giving up, or forever if not set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_attempts: Option<u32>,
+    /// Disconnect reasons containing any of these aren't retried, e.g. bans.
+    pub stop_reasons: Vec<String>,
+    /// Disconnect reasons containing any of these wait at least
+    /// `busy_delay_secs`, e.g. a full server.
+    pub busy_reasons: Vec<String>,
+    pub busy_delay_secs: u64,
+}
+
+impl Default for ReconnectConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            initial_delay_secs: 5,
+            multiplier: 2.0,
+            max_delay_secs: 300,
+            jitter: 0.2,
+            max_attempts: None,
+            stop_reasons: vec![
+                "banned".to_owned(),
+                "white-listed".to_owned(),
+                "whitelist".to_owned(),
+            ],
+            busy_reasons: vec![
+                "server is full".to_owned(),
+                "throttled".to_owned(),
+                "logging in too fast".to_owned(),
+            ],
+            busy_delay_secs: 60,
+        }
+    }
+}
+
+impl ReconnectConfig {
+    fn validate(&self) -> Result<()> {
+        if !(self.multiplier >= 1.0) {
+            bail!("reconnect.multiplier can't be less than 1");
+        }
+        if !(0.0..=1.0).contains(&self.jitter) {
+            bail!("reconnect.jitter has to be between 0 and 1");
+        }
+        if self.initial_delay_secs > self.max_delay_secs {
+            bail!("reconnect.initial_delay_secs can't be more than max_delay_secs");
+        }
+        if self.max_attempts == Some(0) {
+            bail!("reconnect.max_attempts can't be 0, set enabled = false instead");
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Cli, Config};
+
+    fn parse(contents: &str) -> Config {
+        Config::parse(contents).unwrap()
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(Config::default().validate().is_ok());
+        let config = parse(
+            r#"
+            address = "localhost:25565"
+            accounts = [{ username = "a" }, { username = "b" }]
+
+            [reconnect]
+            jitter = 0.0
+            multiplier = 1.0
+            "#,
+        );
+        assert!(config.validate().is_ok());
+
+        for invalid in [
+            "address = \"\"",
+            "accounts = []",
+            "accounts = [{ username = \"a\" }, { username = \"A\" }]",
+            "command_prefix = \"! \"",
+            "reply_mode = \"console\"",
+            "view_distance = 1",
+            "[mining_targets]\ncandidates = 0",
+            "[reconnect]\njitter = 1.5",
+            "[reconnect]\njitter = -0.1",
+            "[reconnect]\nmultiplier = 0.5",
+            "[reconnect]\ninitial_delay_secs = 600\nmax_delay_secs = 300",
+            "[reconnect]\nmax_attempts = 0",
+        ] {
+            assert!(parse(invalid).validate().is_err(), "{invalid} is valid");
+        }
+        assert!(Config::parse("view_distance = \"far\"").is_err());
+    }
+
+    #[test]
+    fn test_cli_apply() {
+        let mut config = parse(
+            r#"
+            address = "localhost:25565"
+            command_prefix = "!"
+            accounts = [{ username = "a" }]
+            "#,
+        );
+        // nothing given on the command line keeps the config as it is
+        let unchanged = config.clone();
+        Cli::default().apply(&mut config);
+        assert_eq!(config, unchanged);
+
+        let cli = Cli {
+            address: Some("example.com".to_owned()),
+            accounts: vec!["b".to_owned(), "c".to_owned()],
+            prefix: Some("?".to_owned()),
+            no_console: true,
+            ..Default::default()
+        };
+        cli.apply(&mut config);
+        assert_eq!(config.address, "example.com");
+        let usernames: Vec<&str> = config
+            .accounts
+            .iter()
+            .map(|account| account.username.as_str())
+            .collect();
+        assert_eq!(usernames, ["b", "c"]);
+        assert_eq!(config.command_prefix, "?");
+        assert!(!config.console.enabled);
+        assert!(config.validate().is_ok());
+    }
+}
@@ -0,0 +1,233 @@
+//! A terminal console that runs the same commands as chat, for when chatting
+//! isn't possible or would spam other players.
+//!
+//! Lines are run on the selected bots, e.g. `@lickbot goto 0 64 0` or
+//! `@lickbot,lickbot2 stop`. A selector on its own line, like `@all`, changes
+//! which bots the following lines run on.
+
+use std::fmt::Display;
+use std::path::PathBuf;
+use std::thread;
+
+use azalea::prelude::*;
+use azalea::swarm::prelude::*;
+use lickbot_plugins::commands::CommandSender;
+use lickbot_plugins::commands::permissions::PermissionLevel;
+use lickbot_plugins::commands::reply::ReplyMode;
+use rustyline::DefaultEditor;
+use rustyline::error::ReadlineError;
+use tokio::sync::mpsc::{self, UnboundedSender};
+use tracing::{debug, info, warn};
+
+use crate::commands::COMMANDS;
+use crate::{State, SwarmState};
+
+/// Which bots a console line runs on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Target {
+    All,
+    Bots(Vec<String>),
+}
+
+impl Target {
+    /// Parses a selector without the `@`, e.g. `all` or `lickbot,lickbot2`.
+    fn parse(selector: &str) -> Self {
+        if selector.eq_ignore_ascii_case("all") {
+            return Target::All;
+        }
+        Target::Bots(
+            selector
+                .split(',')
+                .filter(|name| !name.is_empty())
+                .map(str::to_owned)
+                .collect(),
+        )
+    }
+
+    fn matches(&self, username: &str) -> bool {
+        match self {
+            Target::All => true,
+            Target::Bots(names) => names.iter().any(|name| name.eq_ignore_ascii_case(username)),
+        }
+    }
+}
+
+impl Display for Target {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Target::All => write!(f, "all"),
+            Target::Bots(names) => write!(f, "{}", names.join(",")),
+        }
+    }
+}
+
+/// Reads commands from stdin and runs them on the bots in the swarm until
+/// stdin is closed.
+pub async fn run(swarm: Swarm, state: SwarmState) {
+    let history_file = state.config.read().console.history_file.clone();
+    let (lines_tx, mut lines_rx) = mpsc::unbounded_channel();
+    // rustyline blocks, so it gets its own thread
+    thread::spawn(move || read_lines(history_file, lines_tx));
+
+    while let Some((target, line)) = lines_rx.recv().await {
+        run_line(&swarm, &target, &line).await;
+    }
+}
+
+async fn run_line(swarm: &Swarm, target: &Target, line: &str) {
+    let Some(name) = line.split_whitespace().next() else {
+        return;
+    };
+    if COMMANDS.get(name).is_none() {
+        println!("unknown command: {name}, try help");
+        return;
+    }
+
+    let bots: Vec<Client> = swarm
+        .clone()
+        .into_iter()
+        .filter(|bot| target.matches(&bot.username()))
+        .collect();
+    if bots.is_empty() {
+        let connected: Vec<String> = swarm
+            .clone()
+            .into_iter()
+            .map(|bot| bot.username())
+            .collect();
+        println!(
+            "no connected bots match {target}, connected: {}",
+            connected.join(", ")
+        );
+        return;
+    }
+
+    for bot in bots {
+        let Some(state) = bot.get_component::<State>() else {
+            continue;
+        };
+        let sender = CommandSender {
+            name: None,
+            uuid: None,
+            level: PermissionLevel::Owner,
+        };
+        // errors are already printed as replies
+        let _ = COMMANDS
+            .run(bot, state, sender, ReplyMode::Console, "", line)
+            .await;
+    }
+}
+
+/// Splits a trimmed console line into the bots it runs on and the command,
+/// which is empty when the line only changes the target.
+fn parse_line<'a>(line: &'a str, target: &Target) -> (Target, &'a str) {
+    match line.strip_prefix('@') {
+        Some(selector_and_command) => {
+            let (selector, command) = selector_and_command
+                .split_once(char::is_whitespace)
+                .unwrap_or((selector_and_command, ""));
+            (Target::parse(selector), command.trim())
+        }
+        None => (target.clone(), line),
+    }
+}
+
+/// Reads lines from stdin with line editing and history, and sends them with
+/// the bots they should run on.
+fn read_lines(history_file: PathBuf, lines: UnboundedSender<(Target, String)>) {
+    let mut editor = match DefaultEditor::new() {
+        Ok(editor) => editor,
+        Err(err) => {
+            warn!("Could not start the console: {err}");
+            return;
+        }
+    };
+    if let Err(err) = editor.load_history(&history_file) {
+        debug!("Could not load console history: {err}");
+    }
+
+    let mut target = Target::All;
+    loop {
+        let line = match editor.readline(&format!("{target}> ")) {
+            Ok(line) => line,
+            // the terminal doesn't send SIGINT while reading a line
+            Err(ReadlineError::Interrupted) => {
+                info!("Exiting");
+                std::process::exit(0);
+            }
+            Err(ReadlineError::Eof) => {
+                info!("Console closed");
+                return;
+            }
+            Err(err) => {
+                warn!("Console stopped: {err}");
+                return;
+            }
+        };
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let _ = editor.add_history_entry(line);
+        if let Err(err) = editor.save_history(&history_file) {
+            debug!("Could not save console history: {err}");
+        }
+
+        let (line_target, command) = parse_line(line, &target);
+        if command.is_empty() {
+            target = line_target;
+            continue;
+        }
+
+        if lines.send((line_target, command.to_owned())).is_err() {
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Target, parse_line};
+
+    fn bots(names: &[&str]) -> Target {
+        Target::Bots(names.iter().map(|name| name.to_string()).collect())
+    }
+
+    #[test]
+    fn test_parse_target() {
+        assert_eq!(Target::parse("all"), Target::All);
+        assert_eq!(Target::parse("ALL"), Target::All);
+        assert_eq!(Target::parse("lickbot"), bots(&["lickbot"]));
+        assert_eq!(
+            Target::parse("lickbot,lickbot2"),
+            bots(&["lickbot", "lickbot2"])
+        );
+        // empty entries are skipped
+        assert_eq!(
+            Target::parse(",lickbot,,lickbot2,"),
+            bots(&["lickbot", "lickbot2"])
+        );
+        assert_eq!(Target::parse(""), bots(&[]));
+
+        assert!(Target::All.matches("lickbot"));
+        assert!(bots(&["LickBot"]).matches("lickbot"));
+        assert!(!bots(&[]).matches("lickbot"));
+    }
+
+    #[test]
+    fn test_parse_line() {
+        let target = bots(&["lickbot"]);
+        assert_eq!(
+            parse_line("goto 0 64 0", &target),
+            (bots(&["lickbot"]), "goto 0 64 0")
+        );
+        assert_eq!(parse_line("@all stop", &target), (Target::All, "stop"));
+        assert_eq!(
+            parse_line("@lickbot,lickbot2   come  ", &target),
+            (bots(&["lickbot", "lickbot2"]), "come")
+        );
+        // just a selector switches the target for the next lines
+        assert_eq!(parse_line("@lickbot2", &target), (bots(&["lickbot2"]), ""));
+        assert_eq!(parse_line("@ stop", &target), (bots(&[]), "stop"));
+    }
+}
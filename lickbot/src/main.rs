@@ -1,49 +1,148 @@
-use std::str::FromStr;
+mod commands;
+mod config;
+mod console;
+mod plugins;
+mod reconnect;
+mod reload;
+
+use std::collections::HashMap;
+use std::sync::Arc;
 use std::thread;
 use std::time::Duration;
 
-use anyhow::{Result, anyhow};
-use azalea::pathfinder::PathfinderOpts;
+use anyhow::Result;
+use azalea::chat::ChatPacket;
 use azalea::pathfinder::debug::PathfinderDebugParticles;
-use azalea::pathfinder::goals::{BlockPosGoal, XZGoal, YGoal};
-use azalea::registry::{Block, Item};
+use azalea::prelude::*;
 use azalea::swarm::prelude::*;
-use azalea::{BlockPos, prelude::*};
-use azalea::{chat::ChatPacket, entity::Position};
-use lickbot_plugins::mining::{CantMineAnyError, MiningExtrasClientExt};
-use lickbot_plugins::plugins::auto_look::{self, AutoLookPlugin};
-use lickbot_plugins::plugins::auto_totem::{self, AutoTotemPlugin};
+use clap::Parser;
+use lickbot_plugins::commands::CommandSender;
+use lickbot_plugins::commands::permissions::Permissions;
+use lickbot_plugins::commands::reply::ReplyMode;
+use lickbot_plugins::container_index::{ContainerIndex, ContainerIndexPlugin, ContainerIndexer};
+use lickbot_plugins::plugins::auto_eat::AutoEatPlugin;
+use lickbot_plugins::plugins::auto_look::AutoLookPlugin;
+use lickbot_plugins::plugins::auto_totem::AutoTotemPlugin;
+use lickbot_plugins::plugins::kill_aura::AutoKillPlugin;
 use lickbot_plugins::plugins::look_when_mining::LookMinePlugin;
-use tracing::{debug, error, info, warn};
+use lickbot_plugins::task_queue::{TaskQueue, TaskQueues};
+use lickbot_plugins::tasks::TaskManager;
+use lickbot_plugins::toggle::PluginStates;
+use parking_lot::{Mutex, RwLock};
+use tracing::{info, warn};
 
-const USERNAMES: [&str; 1] = ["lickbot"];
-const ADDRESS: &str = "localhost:25555";
-const PATHFINDER_DEBUG_PARTICLES: bool = true;
+use crate::commands::{COMMANDS, resume_queue};
+use crate::config::{Cli, Config};
+use crate::plugins::apply_plugins;
+use crate::reconnect::Reconnects;
 
 #[derive(Debug, Component, Clone, Default)]
-pub struct State {}
+pub struct State {
+    pub config: Arc<RwLock<Config>>,
+    pub permissions: Arc<RwLock<Permissions>>,
+    /// Shared with the bot's next connections, so toggles aren't lost.
+    pub plugins: Arc<RwLock<PluginStates>>,
+    pub tasks: TaskManager,
+    /// Commands to run after the current task, saved to disk.
+    pub queue: TaskQueue,
+    pub reconnects: Reconnects,
+    /// What's in the containers every bot opened, saved to disk.
+    pub containers: ContainerIndex,
+}
+
+impl State {
+    pub fn new(swarm_state: &SwarmState, username: &str) -> Self {
+        Self {
+            config: swarm_state.config.clone(),
+            permissions: swarm_state.permissions.clone(),
+            plugins: swarm_state.plugins_of(username),
+            tasks: TaskManager::new(),
+            queue: swarm_state.queues.bot(username),
+            reconnects: swarm_state.reconnects.clone(),
+            containers: swarm_state.containers.clone(),
+        }
+    }
+}
 
 #[derive(Debug, Resource, Clone, Default)]
-pub struct SwarmState {}
+pub struct SwarmState {
+    pub cli: Arc<Cli>,
+    pub config: Arc<RwLock<Config>>,
+    pub permissions: Arc<RwLock<Permissions>>,
+    /// The plugin states of each bot, by username.
+    pub plugins: Arc<Mutex<HashMap<String, Arc<RwLock<PluginStates>>>>>,
+    pub queues: TaskQueues,
+    pub reconnects: Reconnects,
+    pub containers: ContainerIndex,
+}
 
-#[tokio::main]
-async fn main() {
+impl SwarmState {
+    /// The plugin states of a bot, starting from the config the first time.
+    pub fn plugins_of(&self, username: &str) -> Arc<RwLock<PluginStates>> {
+        self.plugins
+            .lock()
+            .entry(username.to_owned())
+            .or_insert_with(|| {
+                let states = self.config.read().plugin_states(username);
+                Arc::new(RwLock::new(states))
+            })
+            .clone()
+    }
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let mut config = Config::load(&cli.config)?;
+    cli.apply(&mut config);
+    config.validate()?;
+
+    if std::env::var_os("RUST_LOG").is_none() {
+        // SAFETY: the runtime isn't built yet, so this is the only thread.
+        // The logger reads this when the swarm starts.
+        unsafe { std::env::set_var("RUST_LOG", &config.log_level) };
+    }
+
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()?
+        .block_on(run(cli, config))
+}
+
+/// How often changes to the container index are saved.
+const CONTAINER_INDEX_FLUSH_INTERVAL: Duration = Duration::from_secs(5);
+
+async fn run(cli: Cli, config: Config) -> Result<()> {
     thread::spawn(deadlock_detection_thread);
 
+    let swarm_state = SwarmState {
+        permissions: Arc::new(RwLock::new(Permissions::load(&config.permissions_file)?)),
+        queues: TaskQueues::load(&config.tasks_file)?,
+        containers: ContainerIndex::load(&config.containers_file)?,
+        config: Arc::new(RwLock::new(config.clone())),
+        cli: Arc::new(cli),
+        plugins: Default::default(),
+        reconnects: Reconnects::default(),
+    };
+
     let mut swarm = SwarmBuilder::new()
+        .add_plugins(AutoEatPlugin)
+        .add_plugins(AutoKillPlugin)
         .add_plugins(AutoLookPlugin)
         .add_plugins(AutoTotemPlugin)
+        .add_plugins(ContainerIndexPlugin)
         .add_plugins(LookMinePlugin)
         .set_handler(handle)
         .set_swarm_handler(swarm_handle)
-        .join_delay(Duration::from_secs(5));
+        .set_swarm_state(swarm_state.clone())
+        .join_delay(config.join_delay());
 
-    for name in USERNAMES {
-        let account = Account::offline(name);
-        swarm = swarm.add_account_with_state(account, State::default());
+    for account in &config.accounts {
+        let state = State::new(&swarm_state, &account.username);
+        let account = Account::offline(&account.username);
+        swarm = swarm.add_account_with_state(account, state);
     }
 
-    swarm.start(ADDRESS).await.unwrap();
+    swarm.start(config.address.as_str()).await?
 }
 
 /// Runs a loop that checks for deadlocks every 10 seconds.
@@ -73,57 +172,88 @@ fn deadlock_detection_thread() {
 async fn handle(bot: Client, event: Event, state: State) -> Result<()> {
     match &event {
         Event::Init => {
-            bot.set_client_information(azalea::ClientInformation {
-                view_distance: 32,
-                ..Default::default()
-            });
-            if PATHFINDER_DEBUG_PARTICLES {
-                bot.ecs
-                    .lock()
-                    .entity_mut(bot.entity)
-                    .insert(PathfinderDebugParticles);
-            }
-        }
-        Event::Spawn => {
-            info!("{} has logged in to world", bot.username());
-            // bot.enable_auto_kill(EntityTargets::new(&[EntityTarget::AllMonsters]));
+            apply_client_config(&bot, &state.config.read());
             bot.ecs
                 .lock()
                 .entity_mut(bot.entity)
-                .insert(auto_totem::AutoTotem);
-            bot.ecs
-                .lock()
-                .entity_mut(bot.entity)
-                .insert(auto_look::AutoLook);
+                .insert(ContainerIndexer {
+                    index: state.containers.clone(),
+                    world: state.config.read().address.clone(),
+                });
+        }
+        Event::Spawn => {
+            let username = bot.username();
+            info!("{username} has logged in to world");
+            state.reconnects.spawned(&username);
+            apply_plugins(&bot, &state);
+            // this also happens on respawns, when the task is still running
+            if state.tasks.running().is_empty() {
+                tokio::spawn(resume_queue(bot.clone(), state.clone()));
+            }
         }
         Event::Chat(chat) => handle_chat(bot, state, chat).await?,
         Event::Death(death) => {
             info!("{} has died! Reason: ```{:?}```", bot.username(), death)
         }
+        Event::Disconnect(reason) => {
+            state
+                .reconnects
+                .disconnected(&bot.username(), reason.as_ref().map(ToString::to_string));
+            // the queue is kept, so the task is resumed after reconnecting
+            state.tasks.cancel_all();
+        }
         _ => {}
     }
     Ok(())
 }
 
-async fn swarm_handle(swarm: Swarm, event: SwarmEvent, _state: SwarmState) -> Result<()> {
+/// Sends the client settings from the config to the server.
+pub fn apply_client_config(bot: &Client, config: &Config) {
+    bot.set_client_information(azalea::ClientInformation {
+        view_distance: config.view_distance,
+        ..Default::default()
+    });
+
+    let mut ecs = bot.ecs.lock();
+    let mut entity = ecs.entity_mut(bot.entity);
+    if config.pathfinder_debug_particles {
+        entity.insert(PathfinderDebugParticles);
+    } else {
+        entity.remove::<PathfinderDebugParticles>();
+    }
+}
+
+async fn swarm_handle(swarm: Swarm, event: SwarmEvent, state: SwarmState) -> Result<()> {
     match &event {
-        SwarmEvent::Disconnect(account, join_opts) => {
-            info!(
-                "{} got disconnected! Reconnecting in 5 seconds",
-                account.username
+        SwarmEvent::Init => {
+            if !state.permissions.read().has_owner() {
+                warn!(
+                    "No owners in {}, only public commands can be used",
+                    state.config.read().permissions_file.display()
+                );
+            }
+            tokio::spawn(reload::watch_files(swarm.clone(), state.clone()));
+            tokio::spawn(
+                state
+                    .containers
+                    .clone()
+                    .flush_periodically(CONTAINER_INDEX_FLUSH_INTERVAL),
             );
-            tokio::time::sleep(Duration::from_millis(500)).await;
-            swarm
-                .add_with_opts(account, State::default(), join_opts)
-                .await
-                .unwrap();
+            if state.config.read().console.enabled {
+                tokio::spawn(console::run(swarm.clone(), state.clone()));
+            }
+        }
+        SwarmEvent::Disconnect(account, join_opts) => {
+            reconnect::reconnect(swarm, state, account, join_opts).await;
         }
         SwarmEvent::Chat(chat) => {
-            if [
-                "The particle was not visible for anybody",
-                "Displaying particle minecraft:dust",
-            ]
-            .contains(&chat.message().to_string().as_str())
+            let message = chat.message().to_string();
+            if state
+                .config
+                .read()
+                .chat_filters
+                .iter()
+                .any(|filter| message.contains(filter.as_str()))
             {
                 return Ok(());
             }
@@ -135,298 +265,28 @@ async fn swarm_handle(swarm: Swarm, event: SwarmEvent, _state: SwarmState) -> Re
     Ok(())
 }
 
-async fn handle_chat(bot: Client, _state: State, chat: &ChatPacket) -> Result<()> {
-    let content = chat.content();
-
-    let parts: Vec<&str> = content.split_whitespace().collect();
-
-    match *parts.as_slice().first().unwrap_or(&"") {
-        "!ping" => {
-            bot.chat("pong!");
-        }
-        "!health" => {
-            let health = bot.health();
-            bot.chat(format!("health: {health}"));
-        }
-        "!hunger" => {
-            let hunger = bot.hunger();
-            bot.chat(format!(
-                "hunger: {}, saturation: {}",
-                hunger.food, hunger.saturation
-            ));
-        }
-        "!pos" => {
-            let pos = bot.position();
-            bot.chat(format!("x: {}, y: {}, z: {}", pos.x, pos.y, pos.z));
-        }
-        "!goto" => match parts.len() {
-            1 => {
-                let error_fn = || {
-                    error!("Got !goto, could not find sender");
-                    anyhow::anyhow!("could not find message sender")
-                };
-                let uuid = chat.sender_uuid().ok_or_else(error_fn)?;
-                let entity = bot.entity_by_uuid(uuid).ok_or_else(error_fn)?;
-                let position = bot
-                    .get_entity_component::<Position>(entity)
-                    .ok_or_else(error_fn)?;
-
-                bot.start_goto_with_opts(BlockPosGoal(position.into()), PathfinderOpts::new());
-
-                info!(
-                    "going to location of {}",
-                    chat.sender().ok_or_else(error_fn)?
-                );
-            }
-            2 => {
-                let y: i32 = parts[1].parse()?;
-                bot.start_goto_with_opts(YGoal { y }, PathfinderOpts::new());
-            }
-            3 => {
-                let x: i32 = parts[1].parse()?;
-                let z: i32 = parts[2].parse()?;
-                bot.start_goto_with_opts(XZGoal { x, z }, PathfinderOpts::new());
-            }
-            4 => {
-                let x: i32 = parts[1].parse()?;
-                let y: i32 = parts[2].parse()?;
-                let z: i32 = parts[3].parse()?;
-                bot.start_goto_with_opts(
-                    BlockPosGoal(BlockPos::new(x, y, z)),
-                    PathfinderOpts::new(),
-                );
-            }
-            _ => {
-                info!("Invalid number of arguments for !goto command");
-                return Err(anyhow!("Invalid number of arguments for !goto command"));
-            }
-        },
-        "!stop" => {
-            bot.stop_pathfinding();
-        }
-        "!mine" => match parts.len() {
-            2 => {
-                let block_name = parts[1];
-                let block = Block::from_str(&format!("minecraft:{block_name}")).map_err(|_| {
-                    info!("Invalid block name: {}", block_name);
-                    anyhow!("Invalid block name: {block_name}")
-                })?;
-                let blocks_pos: Vec<BlockPos> = bot
-                    .world()
-                    .read()
-                    .find_blocks(bot.position(), &block.into())
-                    .take(10)
-                    .collect();
-                if blocks_pos.is_empty() {
-                    info!("Could not find block nearby: {}", block_name);
-                    return Err(anyhow!("Could not find block nearby: {block_name}"));
-                }
-                info!("Mining block {} at positions {:?}", block, blocks_pos);
-
-                bot.goto_and_try_mine_blocks(&blocks_pos).await?;
-            }
-            3 => {
-                let block_name = parts[1];
-                let block = Block::from_str(&format!("minecraft:{block_name}")).map_err(|_| {
-                    info!("Invalid block name: {}", block_name);
-                    anyhow!("Invalid block name: {block_name}")
-                })?;
-
-                let item_name = parts[2];
-                let item = Item::from_str(&format!("minecraft:{item_name}")).map_err(|_| {
-                    info!("Invalid item name: {}", item_name);
-                    anyhow!("Invalid item name: {item_name}")
-                })?;
-
-                let blocks_pos: Vec<BlockPos> = bot
-                    .world()
-                    .read()
-                    .find_blocks(bot.position(), &block.into())
-                    .take(10)
-                    .collect();
-                if blocks_pos.is_empty() {
-                    info!("Could not find block nearby: {}", block_name);
-                    return Err(anyhow!("Could not find block nearby: {block_name}"));
-                }
-                info!("Mining block {} at positions {:?}", block, blocks_pos);
-                bot.goto_and_try_mine_blocks(&blocks_pos).await?;
-
-                // wait for the item to drop first
-                bot.wait_ticks(5).await;
-
-                match bot.pick_up_item(item).await {
-                    Ok(_) => (),
-                    Err(_) => {
-                        warn!("Could not find item: {item}")
-                    }
-                }
-            }
-            4 => {
-                let x: i32 = parts[1].parse()?;
-                let y: i32 = parts[2].parse()?;
-                let z: i32 = parts[3].parse()?;
-                let pos = BlockPos::new(x, y, z);
-                info!("Mining at position: {:?}", pos);
-
-                bot.goto_and_try_mine_block(&pos).await?;
-            }
-            _ => {
-                info!("Invalid number of arguments for !mine command");
-                return Err(anyhow!("Invalid number of arguments for !mine command"));
-            }
-        },
-        "!mineall" => match parts.len() {
-            2 => {
-                let block_name = parts[1];
-                let block = Block::from_str(&format!("minecraft:{block_name}")).map_err(|_| {
-                    info!("Invalid block name: {}", block_name);
-                    anyhow!("Invalid block name: {block_name}")
-                })?;
-
-                loop {
-                    let blocks_pos: Vec<BlockPos> = bot
-                        .world()
-                        .read()
-                        .find_blocks(bot.position(), &block.into())
-                        .take(10)
-                        .collect();
-                    if blocks_pos.is_empty() {
-                        info!("Could not find block nearby: {}", block_name);
-                        return Err(anyhow!("Could not find block nearby: {block_name}"));
-                    }
-                    info!("Mining block {} at positions {:?}", block, blocks_pos);
-
-                    bot.goto_and_try_mine_blocks(&blocks_pos).await?;
-                }
-            }
-            3 => {
-                let block_name = parts[1];
-                let block = Block::from_str(&format!("minecraft:{block_name}")).map_err(|_| {
-                    info!("Invalid block name: {}", block_name);
-                    anyhow!("Invalid block name: {block_name}")
-                })?;
-
-                let item_name = parts[2];
-                let item = Item::from_str(&format!("minecraft:{item_name}")).map_err(|_| {
-                    info!("Invalid item name: {}", item_name);
-                    anyhow!("Invalid item name: {item_name}")
-                })?;
-
-                loop {
-                    // mine a block
-                    let blocks_pos: Vec<BlockPos> = bot
-                        .world()
-                        .read()
-                        .find_blocks(bot.position(), &block.into())
-                        .take(10)
-                        .collect();
-                    if blocks_pos.is_empty() {
-                        info!("Could not find block nearby: {}", block_name);
-                        return Err(anyhow!("Could not find block nearby: {block_name}"));
-                    }
-                    info!("Mining block {} at positions {:?}", block, blocks_pos);
-                    bot.goto_and_try_mine_blocks(&blocks_pos).await?;
-
-                    // then, try to mine all other blocks it can reach
-                    let blocks_pos: Vec<BlockPos> = bot
-                        .world()
-                        .read()
-                        .find_blocks(bot.position(), &block.into())
-                        .take(15)
-                        .collect();
-
-                    #[allow(clippy::while_let_loop)]
-                    loop {
-                        match bot.mine_blocks_with_best_tool(&blocks_pos).await {
-                            Ok(()) => (),
-                            Err(CantMineAnyError) => break,
-                        }
-                    }
-
-                    // wait for the items to drop
-                    bot.wait_ticks(4).await;
-
-                    // then pick up all the items dropped
-                    #[allow(clippy::while_let_loop)]
-                    loop {
-                        debug!("picking up item: {item}");
-                        match bot.pick_up_item(item).await {
-                            Ok(_) => (),
-                            Err(_) => break,
-                        }
-                    }
-
-                    debug!("restarting mining loop");
-
-                    bot.wait_updates(1).await; // just in case lmao
-                }
-            }
-            _ => {
-                info!("Incorrect  arguments for !mineall command");
-                return Err(anyhow!("Incorrect arguments for !mineall command"));
-            }
-        },
-        "!pickup" => match parts.len() {
-            2 => {
-                let item_name = parts[1];
-                let item = Item::from_str(&format!("minecraft:{item_name}")).map_err(|_| {
-                    info!("Invalid item name: {}", item_name);
-                    anyhow!("Invalid item name: {item_name}")
-                })?;
-                info!("Picking up item: {}", item);
-
-                bot.pick_up_item(item).await?;
-            }
-            _ => {
-                info!("Incorrect arguments for !pickup command");
-                return Err(anyhow!("Incorrect arguments for !pickup command"));
-            }
-        },
-        // "!killaura" => match parts.get(1) {
-        //     Some(&"on") => {
-        //         let target = match parts.get(2) {
-        //             Some(&"hostile") => EntityTarget::AllMonsters,
-        //             Some(&"players") => EntityTarget::AllPlayers,
-        //             Some(&"entity") => {
-        //                 let entity_name = parts.get(3).ok_or_else(|| {
-        //                     error!("!killaura entity requires an entity name");
-        //                     anyhow!("!killaura entity requires an entity name")
-        //                 })?;
-        //                 EntityTarget::EntityKind(
-        //                     EntityKind::from_str(&("minecraft:".to_owned() + *entity_name))
-        //                         .map_err(|_| {
-        //                             error!("Invalid entity name: {}", entity_name);
-        //                             anyhow!("Invalid entity name: {}", entity_name)
-        //                         })?,
-        //                 )
-        //             }
-        //             Some(&"player") => {
-        //                 let player_name = parts.get(3).ok_or_else(|| {
-        //                     error!("!killaura player requires a player name");
-        //                     anyhow!("!killaura player requires a player name")
-        //                 })?;
-        //                 EntityTarget::PlayerName(player_name.to_string())
-        //             }
-        //             _ => {
-        //                 info!("Invalid arguments for !killaura command");
-        //                 return Err(anyhow!("Invalid arguments for !killaura command"));
-        //             }
-        //         };
+async fn handle_chat(bot: Client, state: State, chat: &ChatPacket) -> Result<()> {
+    let name = chat.sender();
+    let uuid = chat.sender_uuid();
+    // don't run our own messages
+    if name.as_deref() == Some(bot.username().as_str()) {
+        return Ok(());
+    }
 
-        //         info!("killaura enabled for target {:?}!", &target);
-        //         bot.enable_auto_kill(EntityTargets::new(&[target]));
-        //     }
-        //     Some(&"off") => {
-        //         bot.disable_auto_kill();
-        //         info!("killaura disabled!");
-        //     }
-        //     _ => {
-        //         info!("Invalid arguments for !killaura command");
-        //         return Err(anyhow!("Invalid arguments for !killaura command"));
-        //     }
-        // },
-        _ => {}
+    let level = state.permissions.read().level(name.as_deref(), uuid);
+    let sender = CommandSender { name, uuid, level };
+    // keep replies to whispers private
+    let (reply_mode, prefix) = {
+        let config = state.config.read();
+        let reply_mode = if chat.is_whisper() {
+            ReplyMode::Whisper
+        } else {
+            config.reply_mode
+        };
+        (reply_mode, config.command_prefix.clone())
     };
 
-    Ok(())
+    COMMANDS
+        .run(bot, state, sender, reply_mode, &prefix, &chat.content())
+        .await
 }
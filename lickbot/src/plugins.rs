@@ -0,0 +1,52 @@
+use std::sync::LazyLock;
+
+use azalea::prelude::*;
+use lickbot_plugins::plugins::auto_eat::AutoEat;
+use lickbot_plugins::plugins::auto_look::AutoLook;
+use lickbot_plugins::plugins::auto_totem::AutoTotem;
+use lickbot_plugins::plugins::kill_aura::AutoKill;
+use lickbot_plugins::toggle::{TogglePlugin, ToggleRegistry};
+use lickbot_plugins::tools::{ToolPolicy, ToolProtection};
+use lickbot_plugins::unload::FullInventoryPolicy;
+use tracing::warn;
+
+use crate::State;
+
+/// Every plugin that can be turned on and off with `!plugin`.
+pub static PLUGINS: LazyLock<ToggleRegistry> = LazyLock::new(|| {
+    let mut registry = ToggleRegistry::new();
+    registry
+        .register(TogglePlugin::new::<AutoEat>("autoeat", "Eats when hungry"))
+        .register(TogglePlugin::new::<AutoKill>(
+            "autokill",
+            "Attacks nearby targets",
+        ))
+        .register(TogglePlugin::new::<AutoLook>(
+            "autolook",
+            "Looks at the nearest player",
+        ))
+        .register(TogglePlugin::new::<AutoTotem>(
+            "autototem",
+            "Keeps a totem in the offhand",
+        ))
+        .register(TogglePlugin::new::<ToolProtection>(
+            "toolprotection",
+            "Doesn't mine with nearly broken tools",
+        ))
+        .register(TogglePlugin::new::<ToolPolicy>(
+            "toolpolicy",
+            "Mines with Fortune or Silk Touch tools only where it's worth it",
+        ))
+        .register(TogglePlugin::new::<FullInventoryPolicy>(
+            "fullinventory",
+            "Drops junk or unloads into a chest when the inventory fills up while mining",
+        ));
+    registry
+});
+
+/// Enables or disables the plugins of a bot to match its state.
+pub fn apply_plugins(bot: &Client, state: &State) {
+    if let Err(err) = PLUGINS.apply(bot, &state.plugins.read()) {
+        warn!("Could not apply plugins to {}: {err}", bot.username());
+    }
+}
@@ -0,0 +1,354 @@
+//! Rejoining the server after a bot is disconnected, waiting longer each time
+//! it fails.
+//!
+//! Attempts and their outcomes are sent as [`ReconnectEvent`]s, which other
+//! code can get with [`Reconnects::subscribe`].
+
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::hash::{BuildHasher, RandomState};
+use std::mem;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use azalea::JoinOpts;
+use azalea::prelude::*;
+use azalea::swarm::prelude::*;
+use parking_lot::Mutex;
+use tokio::sync::{Notify, broadcast};
+use tracing::{debug, info, warn};
+
+use crate::config::ReconnectConfig;
+use crate::{State, SwarmState};
+
+/// How long to wait for the bot's handler to record why it was disconnected,
+/// in case it never gets the event.
+const REASON_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Something that happened while reconnecting a bot.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReconnectEvent {
+    Disconnected {
+        username: String,
+        reason: Option<String>,
+    },
+    /// Waiting before trying to join again.
+    Scheduled {
+        username: String,
+        attempt: u32,
+        delay: Duration,
+    },
+    /// Joining failed, another attempt will be scheduled unless it gives up.
+    Failed {
+        username: String,
+        attempt: u32,
+        error: String,
+    },
+    /// The bot spawned in the world again.
+    Reconnected { username: String, attempts: u32 },
+    /// The bot won't be reconnected.
+    GaveUp {
+        username: String,
+        reason: GiveUpReason,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum GiveUpReason {
+    Disabled,
+    /// The disconnect reason matched one of the stop reasons.
+    Kicked(String),
+    MaxAttempts(u32),
+}
+
+impl Display for GiveUpReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GiveUpReason::Disabled => write!(f, "reconnecting is disabled"),
+            GiveUpReason::Kicked(reason) => write!(f, "disconnected for {reason:?}"),
+            GiveUpReason::MaxAttempts(attempts) => write!(f, "failed {attempts} times"),
+        }
+    }
+}
+
+/// What to do after a failed connection.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Decision {
+    Retry { delay: Duration },
+    GiveUp(GiveUpReason),
+}
+
+/// Decides whether to make attempt number `attempt`, and how long to wait
+/// first. `random` is between 0 and 1 and picks the jitter.
+pub fn decide(
+    config: &ReconnectConfig,
+    attempt: u32,
+    reason: Option<&str>,
+    random: f64,
+) -> Decision {
+    if !config.enabled {
+        return Decision::GiveUp(GiveUpReason::Disabled);
+    }
+
+    let reason = reason.unwrap_or_default();
+    let lowercase_reason = reason.to_lowercase();
+    let matches = |patterns: &[String]| {
+        patterns
+            .iter()
+            .any(|pattern| lowercase_reason.contains(&pattern.to_lowercase()))
+    };
+    if matches(&config.stop_reasons) {
+        return Decision::GiveUp(GiveUpReason::Kicked(reason.to_owned()));
+    }
+    if let Some(max_attempts) = config.max_attempts
+        && attempt > max_attempts
+    {
+        return Decision::GiveUp(GiveUpReason::MaxAttempts(max_attempts));
+    }
+
+    let backoff =
+        config.initial_delay_secs as f64 * config.multiplier.powi(attempt.saturating_sub(1) as i32);
+    let mut delay = backoff.min(config.max_delay_secs as f64);
+    if matches(&config.busy_reasons) {
+        delay = delay.max(config.busy_delay_secs as f64);
+    }
+    let jitter = 1.0 + config.jitter * (random * 2.0 - 1.0);
+
+    Decision::Retry {
+        delay: Duration::from_secs_f64(delay * jitter),
+    }
+}
+
+/// A number between 0 and 1 that's different every call. Good enough for
+/// spreading out reconnects, without pulling in `rand`.
+fn random() -> f64 {
+    let hash = RandomState::new().hash_one(Instant::now());
+    hash as f64 / u64::MAX as f64
+}
+
+#[derive(Debug, Default)]
+struct AccountReconnects {
+    /// Failed attempts since the bot last spawned.
+    attempts: u32,
+    last_reason: Option<String>,
+    /// Whether the bot's handler recorded a disconnect that no reconnect has
+    /// waited for yet.
+    unhandled_disconnect: bool,
+}
+
+/// The reconnect attempts of every bot, by username.
+#[derive(Debug, Clone)]
+pub struct Reconnects {
+    accounts: Arc<Mutex<HashMap<String, AccountReconnects>>>,
+    events: broadcast::Sender<ReconnectEvent>,
+    /// Notified whenever a disconnect is recorded.
+    recorded: Arc<Notify>,
+}
+
+impl Default for Reconnects {
+    fn default() -> Self {
+        Self {
+            accounts: Default::default(),
+            events: broadcast::channel(64).0,
+            recorded: Default::default(),
+        }
+    }
+}
+
+impl Reconnects {
+    /// Receives the events sent after this is called.
+    pub fn subscribe(&self) -> broadcast::Receiver<ReconnectEvent> {
+        self.events.subscribe()
+    }
+
+    /// Records why a bot was disconnected, called from its own handler.
+    pub fn disconnected(&self, username: &str, reason: Option<String>) {
+        {
+            let mut accounts = self.accounts.lock();
+            let account = accounts.entry(username.to_owned()).or_default();
+            account.last_reason = reason.clone();
+            account.unhandled_disconnect = true;
+        }
+        self.recorded.notify_waiters();
+        self.send(ReconnectEvent::Disconnected {
+            username: username.to_owned(),
+            reason,
+        });
+    }
+
+    /// Resets the attempts of a bot once it's in the world.
+    pub fn spawned(&self, username: &str) {
+        let Some(account) = self.accounts.lock().remove(username) else {
+            return;
+        };
+        if account.attempts > 0 {
+            self.send(ReconnectEvent::Reconnected {
+                username: username.to_owned(),
+                attempts: account.attempts,
+            });
+        }
+    }
+
+    /// Waits until the bot's handler has recorded why it was disconnected,
+    /// since the swarm can get the disconnect before or after it does.
+    async fn wait_for_reason(&self, username: &str) {
+        loop {
+            // created before checking, so a disconnect recorded in between
+            // still wakes it up
+            let recorded = self.recorded.notified();
+            if let Some(account) = self.accounts.lock().get_mut(username)
+                && mem::take(&mut account.unhandled_disconnect)
+            {
+                return;
+            }
+            recorded.await;
+        }
+    }
+
+    /// Counts an attempt and returns its number and the last disconnect
+    /// reason.
+    fn next_attempt(&self, username: &str) -> (u32, Option<String>) {
+        let mut accounts = self.accounts.lock();
+        let account = accounts.entry(username.to_owned()).or_default();
+        account.attempts += 1;
+        (account.attempts, account.last_reason.clone())
+    }
+
+    fn failed(&self, username: &str, error: String) {
+        self.accounts
+            .lock()
+            .entry(username.to_owned())
+            .or_default()
+            .last_reason = Some(error);
+    }
+
+    fn send(&self, event: ReconnectEvent) {
+        match &event {
+            ReconnectEvent::Disconnected { username, reason } => match reason {
+                Some(reason) => info!("{username} was disconnected: {reason}"),
+                None => info!("{username} was disconnected"),
+            },
+            ReconnectEvent::Scheduled {
+                username,
+                attempt,
+                delay,
+            } => info!(
+                "Reconnecting {username} in {:.1} seconds (attempt {attempt})",
+                delay.as_secs_f64()
+            ),
+            ReconnectEvent::Failed {
+                username,
+                attempt,
+                error,
+            } => warn!("Attempt {attempt} to reconnect {username} failed: {error}"),
+            ReconnectEvent::Reconnected { username, attempts } => {
+                info!("{username} reconnected after {attempts} attempts")
+            }
+            ReconnectEvent::GaveUp { username, reason } => {
+                warn!("Not reconnecting {username}, {reason}")
+            }
+        }
+        // no receivers is fine
+        let _ = self.events.send(event);
+    }
+}
+
+/// Tries to rejoin with a disconnected account until it joins or the policy
+/// gives up.
+pub async fn reconnect(swarm: Swarm, state: SwarmState, account: &Account, join_opts: &JoinOpts) {
+    let username = &account.username;
+    let reconnects = &state.reconnects;
+    if tokio::time::timeout(REASON_TIMEOUT, reconnects.wait_for_reason(username))
+        .await
+        .is_err()
+    {
+        debug!("{username} was disconnected without a reason being recorded");
+    }
+
+    loop {
+        let (attempt, reason) = reconnects.next_attempt(username);
+        let config = state.config.read().reconnect.clone();
+        let delay = match decide(&config, attempt, reason.as_deref(), random()) {
+            Decision::Retry { delay } => delay,
+            Decision::GiveUp(reason) => {
+                reconnects.send(ReconnectEvent::GaveUp {
+                    username: username.clone(),
+                    reason,
+                });
+                return;
+            }
+        };
+
+        reconnects.send(ReconnectEvent::Scheduled {
+            username: username.clone(),
+            attempt,
+            delay,
+        });
+        tokio::time::sleep(delay).await;
+
+        match swarm
+            .add_with_opts(account, State::new(&state, username), join_opts)
+            .await
+        {
+            // it's counted as reconnected once it spawns, and this runs
+            // again if it's kicked before that
+            Ok(_) => return,
+            Err(err) => {
+                let error = err.to_string();
+                reconnects.send(ReconnectEvent::Failed {
+                    username: username.clone(),
+                    attempt,
+                    error: error.clone(),
+                });
+                reconnects.failed(username, error);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{Decision, GiveUpReason, decide};
+    use crate::config::ReconnectConfig;
+
+    #[test]
+    fn test_decide() {
+        let config = ReconnectConfig {
+            jitter: 0.0,
+            max_attempts: Some(5),
+            ..Default::default()
+        };
+        let delay = |attempt, reason| match decide(&config, attempt, reason, 0.5) {
+            Decision::Retry { delay } => delay,
+            decision => panic!("expected a retry, got {decision:?}"),
+        };
+
+        assert_eq!(delay(1, None), Duration::from_secs(5));
+        assert_eq!(delay(3, Some("Timed out")), Duration::from_secs(20));
+        assert_eq!(
+            delay(1, Some("The server is full!")),
+            Duration::from_secs(60)
+        );
+        assert_eq!(
+            decide(&config, 6, None, 0.5),
+            Decision::GiveUp(GiveUpReason::MaxAttempts(5))
+        );
+        assert!(matches!(
+            decide(&config, 1, Some("You are banned from this server."), 0.5),
+            Decision::GiveUp(GiveUpReason::Kicked(_))
+        ));
+
+        let config = ReconnectConfig {
+            jitter: 0.5,
+            ..Default::default()
+        };
+        assert_eq!(
+            decide(&config, 20, None, 1.0),
+            Decision::Retry {
+                delay: Duration::from_secs(450)
+            }
+        );
+    }
+}
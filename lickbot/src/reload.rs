@@ -0,0 +1,213 @@
+//! Applies changes to the config and permissions files while the swarm is
+//! running.
+
+use std::fs;
+use std::mem;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use anyhow::Result;
+use azalea::swarm::prelude::*;
+use lickbot_plugins::commands::permissions::Permissions;
+use tracing::{debug, info, warn};
+
+use crate::config::{Cli, Config};
+use crate::plugins::apply_plugins;
+use crate::{State, SwarmState, apply_client_config};
+
+/// How often the files are checked for changes.
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Watches the config and permissions files and reloads them when they
+/// change. Invalid edits are logged and ignored.
+pub async fn watch_files(swarm: Swarm, state: SwarmState) {
+    let config_path = state.cli.config.clone();
+    let permissions_path = state.config.read().permissions_file.clone();
+
+    let mut config_file = WatchedFile::new(config_path.clone());
+    let mut permissions_file = WatchedFile::new(permissions_path.clone());
+    // what the last valid config file looked like, to log what changed
+    let mut config_contents = fs::read_to_string(&config_path).unwrap_or_default();
+
+    let mut interval = tokio::time::interval(POLL_INTERVAL);
+    loop {
+        interval.tick().await;
+
+        if config_file.poll() {
+            reload_config(&swarm, &state, &mut config_contents);
+        }
+        if permissions_file.poll() {
+            reload_permissions(&state, &permissions_path);
+        }
+    }
+}
+
+fn reload_config(swarm: &Swarm, state: &SwarmState, last_contents: &mut String) {
+    let path = &state.cli.config;
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(err) => {
+            warn!(
+                "Could not read {}, keeping the old config: {err}",
+                path.display()
+            );
+            return;
+        }
+    };
+    if contents == *last_contents {
+        return;
+    }
+    // only for logging, lines moved between tables don't show up in it
+    let diff = diff_lines(last_contents, &contents);
+
+    let new_config = match parse_config(&contents, &state.cli) {
+        Ok(config) => config,
+        Err(err) => {
+            warn!(
+                "Rejected changes to {}, keeping the old config: {err:#}\n{diff}",
+                path.display()
+            );
+            return;
+        }
+    };
+
+    *last_contents = contents;
+    if new_config == *state.config.read() {
+        debug!("{} was saved without changes to the config", path.display());
+        return;
+    }
+    info!("Reloaded {}:\n{diff}", path.display());
+    let old_config = mem::replace(&mut *state.config.write(), new_config.clone());
+    for setting in old_config.changes_needing_restart(&new_config) {
+        warn!("{setting} was changed, restart to apply it");
+    }
+
+    // plugins toggled with commands stay as they are, unless the config for
+    // them changed
+    for (username, plugins) in state.plugins.lock().iter() {
+        plugins.write().update(
+            &old_config.plugin_states(username),
+            &new_config.plugin_states(username),
+        );
+    }
+
+    for bot in swarm.clone() {
+        apply_client_config(&bot, &new_config);
+        if let Some(bot_state) = bot.get_component::<State>() {
+            apply_plugins(&bot, &bot_state);
+        }
+    }
+}
+
+/// Parses a config file with the command line arguments applied, like at
+/// startup.
+fn parse_config(contents: &str, cli: &Cli) -> Result<Config> {
+    let mut config = Config::parse(contents)?;
+    cli.apply(&mut config);
+    config.validate()?;
+    Ok(config)
+}
+
+fn reload_permissions(state: &SwarmState, path: &Path) {
+    let permissions = match Permissions::load(path) {
+        Ok(permissions) => permissions,
+        Err(err) => {
+            warn!(
+                "Rejected changes to {}, keeping the old permissions: {err}",
+                path.display()
+            );
+            return;
+        }
+    };
+
+    let mut current = state.permissions.write();
+    if *current == permissions {
+        // probably saved by a command
+        debug!("{} was saved without changes", path.display());
+        return;
+    }
+    *current = permissions;
+    info!("Reloaded {}", path.display());
+}
+
+/// A file that is checked for changes by its modification time.
+struct WatchedFile {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+}
+
+impl WatchedFile {
+    fn new(path: PathBuf) -> Self {
+        let modified = modified_time(&path);
+        Self { path, modified }
+    }
+
+    /// Returns whether the file changed since the last poll.
+    fn poll(&mut self) -> bool {
+        let modified = modified_time(&self.path);
+        if modified == self.modified {
+            return false;
+        }
+        self.modified = modified;
+        true
+    }
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|meta| meta.modified()).ok()
+}
+
+/// A rough diff of two files, listing the lines that were removed and added.
+fn diff_lines(old: &str, new: &str) -> String {
+    let old_lines: Vec<&str> = old.lines().collect();
+    let new_lines: Vec<&str> = new.lines().collect();
+
+    let removed = old_lines
+        .iter()
+        .filter(|line| !new_lines.contains(line))
+        .map(|line| format!("- {line}"));
+    let added = new_lines
+        .iter()
+        .filter(|line| !old_lines.contains(line))
+        .map(|line| format!("+ {line}"));
+
+    removed.chain(added).collect::<Vec<_>>().join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{diff_lines, parse_config};
+    use crate::config::Cli;
+
+    #[test]
+    fn test_diff_lines() {
+        let old = "address = \"a\"\nview_distance = 8\n";
+        let new = "address = \"b\"\nview_distance = 8\n";
+        assert_eq!(diff_lines(old, new), "- address = \"a\"\n+ address = \"b\"");
+        assert_eq!(diff_lines(old, old), "");
+        // moved lines aren't in the diff, so it can't decide whether to reload
+        let moved = "view_distance = 8\naddress = \"a\"\n";
+        assert_eq!(diff_lines(old, moved), "");
+    }
+
+    #[test]
+    fn test_parse_config() {
+        let cli = Cli::default();
+        let config = parse_config("view_distance = 8", &cli).unwrap();
+        assert_eq!(config.view_distance, 8);
+
+        // invalid edits are rejected rather than applied
+        assert!(parse_config("view_distance = 100", &cli).is_err());
+        assert!(parse_config("command_prefix = \"\"", &cli).is_err());
+        assert!(parse_config("view_distance = ", &cli).is_err());
+        assert!(parse_config("accounts = []", &cli).is_err());
+
+        // command line arguments still apply
+        let cli = Cli {
+            address: Some("example.com".to_owned()),
+            ..Default::default()
+        };
+        let config = parse_config("address = \"localhost\"", &cli).unwrap();
+        assert_eq!(config.address, "example.com");
+    }
+}